@@ -0,0 +1,139 @@
+//! 奥付（底本の記述）を扱うためのモジュールです。
+//!
+//! 青空文庫のテキストの末尾に記述される、底本、底本の親本、初出、入力、校正、作成日などの情報が奥付に対応します。
+
+#[cfg(test)]
+mod test;
+
+/// 底本や底本の親本として挙げられた書籍の情報です。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceBook<'s> {
+    /// 書名や叢書名を含む、書籍を示す記述です。（例：`「銀河鉄道の夜」新潮文庫、新潮社`）
+    pub name: &'s str,
+    /// 出版社です。書籍の記述の最後の`、`より後ろを出版社とみなします。
+    pub publisher: Option<&'s str>,
+    /// 発行や増刷の日付の記述です。（例：`1989（平成元）年6月15日発行`）
+    pub editions: Vec<&'s str>,
+}
+
+/// 青空文庫のテキスト末尾の奥付をまとめた型です。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Colophon<'s> {
+    /// 底本です。
+    pub source: Option<SourceBook<'s>>,
+    /// 底本の親本です。
+    pub parent_source: Option<SourceBook<'s>>,
+    /// 初出の記述です。
+    pub first_appearance: Vec<&'s str>,
+    /// 入力を担当した人です。
+    pub input: Vec<&'s str>,
+    /// 校正を担当した人です。
+    pub proofreading: Vec<&'s str>,
+    /// ファイルの作成日や修正日の記述です。（例：`2005年1月1日作成`）
+    pub history: Vec<&'s str>,
+    /// 上記のいずれにも当てはまらない記述です。
+    pub notes: Vec<&'s str>,
+    /// 奥付全体の原文です。
+    pub raw: &'s str,
+}
+
+/// 直前に読んだ見出しです。字下げされた続きの行をどこに加えるかを決めるために用います。
+#[derive(Clone, Copy)]
+enum Field {
+    Source,
+    ParentSource,
+    FirstAppearance,
+    Other,
+}
+
+/// `text`の末尾にある奥付を解析し、奥付と、奥付より前の本文を返します。
+///
+/// 行頭の`底本：`から始まる最後のまとまりを奥付とみなします。見つからなければ`None`を返します。
+pub fn parse_colophon<'s>(text: &'s str) -> Option<(Colophon<'s>, &'s str)> {
+    let start = if text.starts_with("底本：") {
+        0
+    } else {
+        text.rfind("\n底本：")? + 1
+    };
+    let raw = &text[start..];
+    let mut colophon = Colophon {
+        raw,
+        ..Default::default()
+    };
+    let mut field = Field::Other;
+
+    for line in raw.lines() {
+        // 字下げされた行は直前の見出しの続き
+        if line.starts_with(['　', ' ', '\t']) {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match field {
+                Field::Source => push_edition(&mut colophon.source, line),
+                Field::ParentSource => push_edition(&mut colophon.parent_source, line),
+                Field::FirstAppearance => colophon.first_appearance.push(line),
+                Field::Other => colophon.notes.push(line),
+            }
+            continue;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        field = Field::Other;
+        match line.split_once('：') {
+            Some(("底本", value)) => {
+                colophon.source = Some(SourceBook::new(value.trim()));
+                field = Field::Source;
+            }
+            Some(("底本の親本", value)) => {
+                colophon.parent_source = Some(SourceBook::new(value.trim()));
+                field = Field::ParentSource;
+            }
+            Some(("初出", value)) => {
+                colophon.first_appearance.push(value.trim());
+                field = Field::FirstAppearance;
+            }
+            Some(("入力", value)) => colophon.input.extend(split_names(value)),
+            Some(("校正", value)) => colophon.proofreading.extend(split_names(value)),
+            _ if is_history(line) => colophon.history.push(line),
+            _ => colophon.notes.push(line),
+        }
+    }
+
+    Some((colophon, &text[..start]))
+}
+
+impl<'s> SourceBook<'s> {
+    fn new(name: &'s str) -> Self {
+        // 書名の括弧の中にある`、`は出版社の区切りとみなさない
+        let after_title = name.rfind('」').map(|i| i + '」'.len_utf8()).unwrap_or(0);
+        let publisher = name[after_title..]
+            .rsplit_once('、')
+            .map(|(_, p)| p.trim())
+            .filter(|p| !p.is_empty());
+        Self {
+            name,
+            publisher,
+            editions: Vec::new(),
+        }
+    }
+}
+
+fn push_edition<'s>(book: &mut Option<SourceBook<'s>>, line: &'s str) {
+    if let Some(book) = book {
+        book.editions.push(line);
+    }
+}
+
+fn split_names(value: &str) -> impl Iterator<Item = &str> {
+    value.split('、').map(str::trim).filter(|n| !n.is_empty())
+}
+
+/// `2005年1月1日作成`や`2011年5月26日修正`のような作成日、修正日の記述であるかを返します。
+fn is_history(line: &str) -> bool {
+    line.starts_with(|c: char| c.is_ascii_digit() || c.is_numeric())
+        && line.contains('年')
+        && ["作成", "修正", "公開"].iter().any(|w| line.ends_with(w))
+}
@@ -0,0 +1,50 @@
+use crate::{Colophon, SourceBook, parse_colophon};
+
+const COLOPHON: &str = "底本：「銀河鉄道の夜」新潮文庫、新潮社
+　　　1989（平成元）年6月15日発行
+　　　1994（平成6）年6月5日11刷
+底本の親本：「新修宮沢賢治全集　第十巻」筑摩書房
+　　　1979（昭和54）年
+入力：土屋隆、野口英司
+校正：noriko saito
+2005年1月1日作成
+2011年5月26日修正
+青空文庫作成ファイル：
+このファイルは、インターネットの図書館、青空文庫で作られました。
+";
+
+#[test]
+fn colophon_test() {
+    let text = format!("題\n著者\n\n本文\n\n{}", COLOPHON);
+    let (colophon, body) = parse_colophon(&text).unwrap();
+    assert_eq!(body, "題\n著者\n\n本文\n\n");
+    assert_eq!(
+        colophon,
+        Colophon {
+            source: Some(SourceBook {
+                name: "「銀河鉄道の夜」新潮文庫、新潮社",
+                publisher: Some("新潮社"),
+                editions: vec!["1989（平成元）年6月15日発行", "1994（平成6）年6月5日11刷"],
+            }),
+            parent_source: Some(SourceBook {
+                name: "「新修宮沢賢治全集　第十巻」筑摩書房",
+                publisher: None,
+                editions: vec!["1979（昭和54）年"],
+            }),
+            first_appearance: Vec::new(),
+            input: vec!["土屋隆", "野口英司"],
+            proofreading: vec!["noriko saito"],
+            history: vec!["2005年1月1日作成", "2011年5月26日修正"],
+            notes: vec![
+                "青空文庫作成ファイル：",
+                "このファイルは、インターネットの図書館、青空文庫で作られました。"
+            ],
+            raw: COLOPHON,
+        }
+    );
+}
+
+#[test]
+fn no_colophon_test() {
+    assert!(parse_colophon("題\n著者\n\n本文の中の底本：という語\n").is_none());
+}
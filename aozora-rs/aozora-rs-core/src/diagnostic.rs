@@ -0,0 +1,295 @@
+//! パイプラインの各段階で発生した警告を、共通の形式で表現するためのモジュールです。
+//!
+//! CLI、LSP、WASMなどの利用側はこの型を通すことで、同じ診断を同じ形で表示できます。
+//! 診断の文言は[`Message`]としてIDと引数だけを持つため、利用側で翻訳して表示することもできます。
+
+#[cfg(test)]
+mod test;
+
+use crate::*;
+
+/// 診断の重大度です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// 出力が意図と異なる可能性が高いものです。
+    Error,
+    /// 出力は可能だが、記法の見直しが望ましいものです。
+    Warning,
+}
+
+impl Severity {
+    /// 表示用の名前を返します。
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Error => "エラー",
+            Self::Warning => "警告",
+        }
+    }
+}
+
+/// テキストの置換1件分です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// 置換対象の範囲です。
+    pub span: Span,
+    /// 置換後の文字列です。
+    pub replacement: String,
+}
+
+/// 診断、注釈、修正案の文言です。
+///
+/// 文章そのものではなく、固定のIDと埋め込む値を持ちます。
+/// [`Message::text`]は既定の日本語の文章を返すので、翻訳する場合は`id`ごとに文章を用意して`args`を埋め込んでください。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// 文言の種類ごとに固定のIDです。診断本体の文言では診断コード、それ以外では[`messages`]の値になります。
+    pub id: &'static str,
+    /// 文言に埋め込む値です。`id`ごとに個数と順番が決まっています。
+    pub args: Vec<String>,
+}
+
+impl Message {
+    /// 文言を構築します。
+    pub fn new(id: &'static str, args: Vec<String>) -> Self {
+        Self { id, args }
+    }
+
+    /// 引数を持たない文言を構築します。
+    pub fn plain(id: &'static str) -> Self {
+        Self {
+            id,
+            args: Vec::new(),
+        }
+    }
+
+    /// 既定の日本語の文章を返します。
+    ///
+    /// `{0}`、`{1}`……の位置に`args`を埋め込みます。未知のIDの場合はIDと引数をそのまま並べます。
+    pub fn text(&self) -> String {
+        let Some(template) = template(self.id) else {
+            return std::iter::once(self.id)
+                .chain(self.args.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" ");
+        };
+        let mut text = template.to_string();
+        for (i, arg) in self.args.iter().enumerate() {
+            text = text.replace(&format!("{{{}}}", i), arg);
+        }
+        text
+    }
+}
+
+impl std::fmt::Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+/// 診断の主たる位置とは別に、関連する位置を指し示す注釈です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    /// 注釈が指し示す範囲です。
+    pub span: Span,
+    /// 注釈の内容です。
+    pub message: Message,
+}
+
+/// 診断を解消するための修正案です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// 修正内容の説明です。
+    pub title: Message,
+    /// 適用する置換です。範囲は互いに重なりません。
+    pub edits: Vec<Edit>,
+}
+
+/// 安定したコードを持つ診断です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// `AZ0001`のような、診断の種類ごとに固定のコードです。
+    pub code: &'static str,
+    /// 重大度です。
+    pub severity: Severity,
+    /// 診断の内容です。`message.id`は`code`と同じです。
+    pub message: Message,
+    /// 診断の主たる位置です。位置を特定できない診断では`None`になります。
+    pub span: Option<Span>,
+    /// 関連する位置への注釈です。
+    pub labels: Vec<Label>,
+    /// 修正案です。
+    pub fix: Option<Fix>,
+}
+
+/// 診断コードの一覧です。
+///
+/// 一度割り当てたコードの意味は変更しません。
+pub mod codes {
+    /// 行内の挟み込み型注記が閉じられていません。
+    pub const UNCLOSED_INLINE_NOTE: &str = "AZ0001";
+    /// 前方参照に失敗しました。
+    pub const BACK_REF_FAILED: &str = "AZ0002";
+    /// ルビ区切りの使用方法が不正です。
+    pub const INVALID_RUBY_DELIMITER_USAGE: &str = "AZ0003";
+    /// 注記の影響範囲が交差しています。
+    pub const CROSSING_NOTE: &str = "AZ0004";
+    /// 開始注記のない終了注記があります。
+    pub const ISOLATED_END_NOTE: &str = "AZ0005";
+    /// 閉じるトークンのない終了命令がありました。
+    pub const INVALID_END_OF_TOKEN: &str = "AZ0006";
+    /// 閉じるスコープのない終了命令がありました。
+    pub const INVALID_END_OF_SCOPE: &str = "AZ0007";
+    /// 認識できない注記です。
+    pub const UNKNOWN_ANNOTATION: &str = "AZ0008";
+    /// 解決できない外字注記です。
+    pub const UNRESOLVED_GAIJI: &str = "AZ0009";
+    /// 参照されたファイルが見つかりません。
+    pub const DEPENDENCY_NOT_FOUND: &str = "AZ0010";
+    /// 対応していない画像形式です。
+    pub const UNSUPPORTED_FIGURE: &str = "AZ0011";
+    /// 【テキスト中に現れる記号について】で宣言されていない記法です。
+    pub const UNDECLARED_NOTATION: &str = "AZ0012";
+}
+
+/// 注記と修正案の文言のIDです。診断本体の文言のIDには[`codes`]の値を用います。
+///
+/// 一度割り当てたIDの意味と引数は変更しません。
+pub mod messages {
+    /// 閉じられていない開始注記を指す注釈です。
+    pub const UNCLOSED_BEGIN: &str = "label.unclosed-begin";
+    /// 範囲が交差している開始注記を指す注釈です。
+    pub const CROSSING_BEGIN: &str = "label.crossing-begin";
+    /// 範囲が交差している終了注記を指す注釈です。
+    pub const CROSSING_END: &str = "label.crossing-end";
+    /// 注記を挿入する修正案です。引数は挿入する注記です。
+    pub const INSERT_NOTE: &str = "fix.insert-note";
+    /// 注記を削除する修正案です。引数は削除する注記です。
+    pub const DELETE_NOTE: &str = "fix.delete-note";
+    /// 前方参照を挟み込み型の注記に書き換える修正案です。引数は開始注記と終了注記の中身です。
+    pub const REWRITE_AS_SANDWICHED: &str = "fix.rewrite-as-sandwiched";
+    /// ルビ区切りを挿入する修正案です。
+    pub const INSERT_RUBY_DELIMITER: &str = "fix.insert-ruby-delimiter";
+}
+
+/// IDに対応する既定の日本語の文章を返します。
+fn template(id: &str) -> Option<&'static str> {
+    Some(match id {
+        codes::UNCLOSED_INLINE_NOTE => "行内注記が閉じられていません",
+        codes::BACK_REF_FAILED => "前方参照に失敗しました",
+        codes::INVALID_RUBY_DELIMITER_USAGE => "ルビの使用方法が不正です",
+        codes::CROSSING_NOTE => "注記が交差しています",
+        codes::ISOLATED_END_NOTE => "開始注記のない終了注記が存在します",
+        codes::INVALID_END_OF_TOKEN => "トークンの終了地点が不正です。これは内部的なエラーです",
+        codes::INVALID_END_OF_SCOPE => {
+            "{0}のスコープの終了地点が不正です。これは内部的なエラーです"
+        }
+        codes::UNKNOWN_ANNOTATION => "不明な注記です：［＃{0}］",
+        codes::UNRESOLVED_GAIJI => "外字を解決できません：※［＃{0}］",
+        codes::DEPENDENCY_NOT_FOUND => "参照されたファイルが見つかりません：{0}",
+        codes::UNSUPPORTED_FIGURE => {
+            "対応していない画像形式です：{0}（png、jpeg、gif、svgのいずれかを使ってください）"
+        }
+        codes::UNDECLARED_NOTATION => {
+            "【テキスト中に現れる記号について】で宣言されていない記法です：{0}（{1}）"
+        }
+        messages::UNCLOSED_BEGIN => "この注記が閉じられていません",
+        messages::CROSSING_BEGIN => "範囲が交差している開始注記",
+        messages::CROSSING_END => "範囲が交差している終了注記",
+        messages::INSERT_NOTE => "{0}を挿入する",
+        messages::DELETE_NOTE => "{0}を削除する",
+        messages::REWRITE_AS_SANDWICHED => "［＃{0}］……［＃{1}］に書き換える",
+        messages::INSERT_RUBY_DELIMITER => "ルビ区切り「｜」を挿入する",
+        _ => return None,
+    })
+}
+
+impl Diagnostic {
+    /// 位置情報、注釈、修正案を持たない診断を構築します。
+    ///
+    /// `args`は`code`の文言に埋め込む値です。
+    pub fn new(code: &'static str, severity: Severity, args: Vec<String>) -> Self {
+        Self {
+            code,
+            severity,
+            message: Message::new(code, args),
+            span: None,
+            labels: Vec::new(),
+            fix: None,
+        }
+    }
+
+    /// 主たる位置を設定します。
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// 関連する位置への注釈を追加します。
+    pub fn with_label(mut self, span: Span, message: Message) -> Self {
+        self.labels.push(Label { span, message });
+        self
+    }
+
+    /// 修正案を設定します。
+    pub fn with_fix(mut self, fix: Option<Fix>) -> Self {
+        self.fix = fix;
+        self
+    }
+
+    /// 診断が持つすべての範囲を`f`で変換します。
+    ///
+    /// 外字の変換前のテキストなど、別のテキスト上の位置で報告するときに用います。
+    pub fn map_spans(mut self, f: impl Fn(&Span) -> Span) -> Self {
+        self.span = self.span.as_ref().map(&f);
+        for label in &mut self.labels {
+            label.span = f(&label.span);
+        }
+        if let Some(fix) = &mut self.fix {
+            for edit in &mut fix.edits {
+                edit.span = f(&edit.span);
+            }
+        }
+        self
+    }
+
+    /// `original`を受け取り、人間に親切な形で診断を表示します。
+    ///
+    /// 範囲は`original`上のバイト位置として解釈されます。文言は既定の日本語の文章で表示します。
+    pub fn display(&self, original: &str) -> String {
+        let category = format!("{}[{}]", self.severity.name(), self.code);
+        let message = self.message.text();
+        let mut displayed = match &self.span {
+            Some(span) => {
+                display_error_with_decolation(original, span.clone(), &category, &message)
+            }
+            None => format!("{}：{}", category, message),
+        };
+        for label in &self.labels {
+            displayed.push('\n');
+            displayed.push_str(&display_error_with_decolation(
+                original,
+                label.span.clone(),
+                "  関連",
+                &label.message.text(),
+            ));
+        }
+        if let Some(fix) = &self.fix {
+            displayed.push_str(&format!("\n  修正案：{}", fix.title));
+        }
+        displayed
+    }
+}
+
+/// `start`から始まる注記（`［＃`から`］`まで）の範囲を返します。
+pub(crate) fn annotation_starting_at(original: &str, start: usize) -> Option<Span> {
+    let rest = original.get(start..)?.strip_prefix("［＃")?;
+    let end = rest.find('］')?;
+    Some(start..start + "［＃".len() + end + "］".len())
+}
+
+/// `end`で終わる注記（`［＃`から`］`まで）の範囲を返します。
+pub(crate) fn annotation_ending_at(original: &str, end: usize) -> Option<Span> {
+    let before = original.get(..end)?.strip_suffix('］')?;
+    let start = before.rfind("［＃")?;
+    Some(start..end)
+}
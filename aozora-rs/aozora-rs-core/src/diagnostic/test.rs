@@ -0,0 +1,91 @@
+use winnow::LocatingSlice;
+
+use crate::{Diagnostic, Edit, ScopenizeError, Severity, codes, messages, scopenize, tokenize};
+
+fn diagnose(input: &str) -> Vec<Diagnostic> {
+    let tokenized = tokenize(&mut LocatingSlice::new(input)).unwrap();
+    let (_, errors) = scopenize(tokenized).into_tuple();
+    errors.iter().map(|e| e.to_diagnostic(input)).collect()
+}
+
+fn apply(input: &str, edits: &[Edit]) -> String {
+    let mut text = input.to_string();
+    for edit in edits.iter().rev() {
+        text.replace_range(edit.span.clone(), &edit.replacement);
+    }
+    text
+}
+
+#[test]
+fn unclosed_test() {
+    let input = "［＃太字］吾輩は猫である\n";
+    let diagnostics = diagnose(input);
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.code, codes::UNCLOSED_INLINE_NOTE);
+    assert_eq!(diagnostic.message.id, codes::UNCLOSED_INLINE_NOTE);
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    // 開始側の注記を関連する位置として指し示す
+    assert_eq!(&input[diagnostic.labels[0].span.clone()], "［＃太字］");
+
+    let fix = diagnostic.fix.as_ref().unwrap();
+    assert_eq!(fix.title.id, messages::INSERT_NOTE);
+    assert_eq!(fix.title.args, ["［＃太字終わり］"]);
+    assert_eq!(fix.title.text(), "［＃太字終わり］を挿入する");
+    assert_eq!(
+        apply(input, &fix.edits),
+        "［＃太字］吾輩は猫である［＃太字終わり］\n"
+    );
+}
+
+#[test]
+fn crossing_test() {
+    let input = "［＃太字］吾輩［＃傍点］は猫［＃太字終わり］である［＃傍点終わり］";
+    let diagnostic = diagnose(input)
+        .into_iter()
+        .find(|d| d.code == codes::CROSSING_NOTE)
+        .unwrap();
+    let labels = diagnostic
+        .labels
+        .iter()
+        .map(|l| &input[l.span.clone()])
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["［＃傍点］", "［＃太字終わり］"]);
+    assert!(diagnostic.fix.is_none());
+}
+
+#[test]
+fn fix_test() {
+    let input = "吾輩は猫である［＃太字終わり］";
+    let fix = diagnose(input).remove(0).fix.unwrap();
+    assert_eq!(apply(input, &fix.edits), "吾輩は猫である");
+
+    let input = "吾輩は猫である。［＃「猫」は太字］";
+    let fix = diagnose(input).remove(0).fix.unwrap();
+    assert_eq!(
+        apply(input, &fix.edits),
+        "吾輩は［＃太字］猫［＃太字終わり］である。"
+    );
+
+    let input = "これはスコップ《しゃべる》です";
+    let fix = diagnose(input).remove(0).fix.unwrap();
+    assert_eq!(apply(input, &fix.edits), "これは｜スコップ《しゃべる》です");
+}
+
+#[test]
+fn display_test() {
+    let input = "吾輩は猫である［＃太字終わり］";
+    let error = ScopenizeError::IsolatedEndNote(21..45);
+    let displayed = error.display(input);
+    assert!(displayed.contains("AZ0005"));
+    assert!(displayed.contains("修正案：［＃太字終わり］を削除する"));
+
+    let diagnostic = Diagnostic::new(
+        codes::INVALID_END_OF_SCOPE,
+        Severity::Error,
+        vec!["太字".to_string()],
+    );
+    let displayed = diagnostic.display(input);
+    assert!(displayed.contains("AZ0007"));
+    assert!(displayed.contains("太字のスコープの終了地点が不正です"));
+}
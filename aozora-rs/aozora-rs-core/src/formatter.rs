@@ -0,0 +1,127 @@
+//! 同じ意味を持つ注記の表記ゆれを正規化するフォーマッタです。
+//!
+//! トークナイズの結果得られた注記ごとに正規の表記を求めるため、
+//! 書き換えの対象はパーサーが解釈できた注記の範囲に限られます。
+
+#[cfg(test)]
+mod test;
+
+use std::borrow::Cow;
+
+use winnow::LocatingSlice;
+
+use crate::*;
+
+/// 「終わり」の表記ゆれ
+const END_VARIANTS: [&str; 2] = ["終り", "おわり"];
+
+/// 半角数字を全角数字に置き換えます。
+fn to_fullwidth_digits(s: &str) -> Cow<'_, str> {
+    if !s.contains(|c: char| c.is_ascii_digit()) {
+        return Cow::Borrowed(s);
+    }
+    s.chars()
+        .map(|c| match c.to_digit(10) {
+            Some(d) if c.is_ascii_digit() => char::from_u32('０' as u32 + d).unwrap(),
+            _ => c,
+        })
+        .collect()
+}
+
+fn normalize_end(content: &str) -> Cow<'_, str> {
+    END_VARIANTS
+        .iter()
+        .find_map(|v| content.strip_suffix(v))
+        .map(|s| Cow::Owned(format!("{}終わり", s)))
+        .unwrap_or(Cow::Borrowed(content))
+}
+
+/// 注記の中身（`［＃`と`］`の間）を、解析結果に応じた正規の表記に書き換えます。
+fn canonical_annotation<'s>(annotation: &Annotation, content: &'s str) -> Cow<'s, str> {
+    match annotation {
+        Annotation::Sandwiched(Sandwiched::End(_)) | Annotation::Multiline(MultiLine::End(_)) => {
+            normalize_end(content)
+        }
+        // 数字以外は固定の表記なので、中身全体の数字を全角にしてよい
+        Annotation::Sandwiched(Sandwiched::Begin(_))
+        | Annotation::Multiline(MultiLine::Begin(_))
+        | Annotation::WholeLine(_) => to_fullwidth_digits(content),
+        Annotation::BackRef(b) => {
+            // 「」で囲まれた対象文字列は本文の一部なので書き換えない
+            let (target, rest) = content.split_at("「".len() + b.range.0.len() + "」".len());
+            let rest = match b.kind {
+                BackRefKind::HinV => Cow::Owned(rest.replace("横一列", "縦中横")),
+                BackRefKind::Small(_) | BackRefKind::Big(_) => to_fullwidth_digits(rest),
+                _ => Cow::Borrowed(rest),
+            };
+            if matches!(rest, Cow::Borrowed(_)) {
+                Cow::Borrowed(content)
+            } else {
+                Cow::Owned(format!("{}{}", target, rest))
+            }
+        }
+        Annotation::Single(Single::PageBreak) => Cow::Borrowed("改ページ"),
+        // 図の大きさは青空文庫でも半角数字で書かれるので対象外とする
+        _ => Cow::Borrowed(content),
+    }
+}
+
+/// テキストを正規化するための置換を、位置の昇順で返します。
+///
+/// 以下の表記ゆれを正規化します。
+/// - 改行コードをLFに統一
+/// - 「終り」「おわり」を「終わり」に統一
+/// - 「改頁」を「改ページ」に統一
+/// - 注記中の数値を全角数字に統一
+/// - 「横一列」を「縦中横」に統一
+pub fn format_edits(text: &str) -> Vec<Edit> {
+    let mut edits = Vec::new();
+
+    let bytes = text.as_bytes();
+    for (i, _) in text.match_indices('\r') {
+        edits.push(Edit {
+            span: i..i + 1,
+            replacement: if bytes.get(i + 1) == Some(&b'\n') {
+                String::new()
+            } else {
+                "\n".to_string()
+            },
+        });
+    }
+
+    let tokens = tokenize(&mut LocatingSlice::new(text)).unwrap_or_default();
+    for token in tokens {
+        let AozoraTokenKind::Annotation(annotation) = &token.kind else {
+            continue;
+        };
+        let span = (token.span.start + "［＃".len())..(token.span.end - "］".len());
+        let content = &text[span.clone()];
+        // 改行をまたぐ注記は改行コードの置換と重なるので触らない
+        if content.contains(['\r', '\n']) {
+            continue;
+        }
+        let canonical = canonical_annotation(annotation, content);
+        if canonical != content {
+            edits.push(Edit {
+                span,
+                replacement: canonical.into_owned(),
+            });
+        }
+    }
+
+    edits.sort_by_key(|e| e.span.start);
+    edits
+}
+
+/// テキスト全体を正規化した結果を返します。
+pub fn format(text: &str) -> String {
+    let mut formatted = String::with_capacity(text.len());
+    let mut last = 0;
+    for edit in format_edits(text) {
+        formatted.push_str(&text[last..edit.span.start]);
+        formatted.push_str(&edit.replacement);
+        last = edit.span.end;
+    }
+    formatted.push_str(&text[last..]);
+    formatted
+}
@@ -0,0 +1,63 @@
+use crate::{Edit, format, format_edits};
+
+#[test]
+fn end_spelling_test() {
+    assert_eq!(
+        format(
+            "［＃太字］吾輩［＃太字終り］\n［＃ここから２字下げ］\n猫\n［＃ここで字下げおわり］\n"
+        ),
+        "［＃太字］吾輩［＃太字終わり］\n［＃ここから２字下げ］\n猫\n［＃ここで字下げ終わり］\n"
+    );
+}
+
+#[test]
+fn page_break_test() {
+    assert_eq!(
+        format("吾輩\n［＃改頁］\n猫\n"),
+        "吾輩\n［＃改ページ］\n猫\n"
+    );
+}
+
+#[test]
+fn digits_test() {
+    assert_eq!(
+        format("［＃3字下げ］［＃ここから12字下げ、折り返して2字下げ］［＃2段階小さな文字］"),
+        "［＃３字下げ］［＃ここから１２字下げ、折り返して２字下げ］［＃２段階小さな文字］"
+    );
+    // 前方参照の対象文字列や図の指定は書き換えない
+    assert_eq!(
+        format("第3章［＃「第3章」は2段階大きな文字］［＃挿絵（fig1.png、横320×縦240）入る］"),
+        "第3章［＃「第3章」は２段階大きな文字］［＃挿絵（fig1.png、横320×縦240）入る］"
+    );
+}
+
+#[test]
+fn hinv_test() {
+    assert_eq!(
+        format("ＡＢ12［＃「12」は横一列］"),
+        "ＡＢ12［＃「12」は縦中横］"
+    );
+}
+
+#[test]
+fn line_ending_test() {
+    assert_eq!(format("吾輩\r\n猫\rである\r\n"), "吾輩\n猫\nである\n");
+}
+
+#[test]
+fn edits_test() {
+    assert_eq!(
+        format_edits("猫\r\n［＃改頁］"),
+        vec![
+            Edit {
+                span: 3..4,
+                replacement: String::new(),
+            },
+            Edit {
+                span: 11..17,
+                replacement: "改ページ".to_string(),
+            },
+        ]
+    );
+    assert!(format_edits("［＃太字］吾輩［＃太字終わり］\n").is_empty());
+}
@@ -1,11 +1,16 @@
 #![warn(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+mod colophon;
 mod deco;
+mod diagnostic;
 mod error;
 mod meta;
 mod nihongo;
+mod path;
+mod symbols;
 
+pub mod formatter;
 pub mod retokenizer;
 pub mod scopenizer;
 pub mod tokenizer;
@@ -17,11 +22,16 @@ pub type Span = std::ops::Range<usize>;
 /// トークナイズに用いるパーサーに共通のエラー型です。
 pub type WinnowError = ();
 
+pub use crate::colophon::*;
 pub use crate::deco::*;
+pub use crate::diagnostic::*;
 
 pub use crate::error::*;
 
+pub use crate::formatter::*;
 pub use crate::meta::*;
+pub use crate::path::*;
 pub use crate::retokenizer::*;
 pub use crate::scopenizer::*;
+pub use crate::symbols::*;
 pub use crate::tokenizer::*;
@@ -2,15 +2,76 @@
 //!
 //! タイトル、著者、【テキスト中に現れる記号について】といったファイルの先頭に記述される情報がメタデータに対応します。
 
+#[cfg(test)]
+mod test;
+
+use std::ops::Range;
+
 use winnow::{Parser, combinator::delimited, error::ContextError, token::take_until};
 
+use crate::{SymbolEntry, parse_symbol_block};
+
 /// 青空文庫で記述されたテキストのメタデータをまとめた型です。
-#[derive(Debug, Clone)]
+///
+/// タイトルと著者以外は省略可能で、EPUBのメタデータとして書き出されます。
+#[derive(Debug, Clone, Default)]
 pub struct AozoraMeta<'s> {
     /// タイトルです。
     pub title: &'s str,
     /// 著者です。
     pub author: &'s str,
+    /// 副題です。
+    pub subtitle: Option<&'s str>,
+    /// 原題です。
+    pub original_title: Option<&'s str>,
+    /// 翻訳者です。
+    pub translator: Option<&'s str>,
+    /// 編者です。
+    pub editor: Option<&'s str>,
+    /// 並べ替えに用いるタイトルの読みです。
+    pub title_reading: Option<&'s str>,
+    /// 並べ替えに用いる著者の読みです。（例：`みやざわ けんじ`）
+    pub author_reading: Option<&'s str>,
+    /// 底本です。
+    pub source: Option<&'s str>,
+    /// 出版者です。
+    pub publisher: Option<&'s str>,
+    /// 権利に関する記述です。
+    pub rights: Option<&'s str>,
+    /// 作品が属する叢書やシリーズです。
+    pub collection: Option<Collection<'s>>,
+    /// 【テキスト中に現れる記号について】に記述された記号です。
+    pub symbols: Vec<SymbolEntry<'s>>,
+    /// 見出しの各行の位置です。
+    pub spans: MetaSpans,
+}
+
+/// 見出しの各行が、[`parse_meta`]に渡したテキストの先頭から見てどの範囲にあるかを表す型です。
+///
+/// 範囲は行の前後の空白を除いた部分を指し、翻訳者と編者は`訳`や`編`を含む行全体を指します。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetaSpans {
+    /// タイトルの行です。
+    pub title: Range<usize>,
+    /// 著者の行です。
+    pub author: Range<usize>,
+    /// 副題の行です。
+    pub subtitle: Option<Range<usize>>,
+    /// 原題の行です。
+    pub original_title: Option<Range<usize>>,
+    /// 翻訳者の行です。
+    pub translator: Option<Range<usize>>,
+    /// 編者の行です。
+    pub editor: Option<Range<usize>>,
+}
+
+/// 作品が属する叢書やシリーズを表す型です。
+#[derive(Debug, Clone)]
+pub struct Collection<'s> {
+    /// 叢書やシリーズの名前です。
+    pub name: &'s str,
+    /// 叢書やシリーズの中での巻数です。
+    pub position: Option<u32>,
 }
 
 /// メタデータ取得中に発生しうるエラーの直和です。
@@ -35,30 +96,177 @@ impl std::fmt::Display for MetaError {
     }
 }
 
+/// 【テキスト中に現れる記号について】を囲む区切り線です。
+const ABOUT_SYMBOL: &str = "-------------------------------------------------------";
+
+/// 見出し部分とみなす最大の行数です。
+const MAX_HEADER_LINES: usize = 8;
+
+/// 翻訳者の行の末尾に付く語です。
+const TRANSLATOR_SUFFIXES: &[&str] = &["訳", "訳注"];
+/// 編者の行の末尾に付く語です。
+const EDITOR_SUFFIXES: &[&str] = &["編", "編集", "編纂"];
+/// 副題の行の先頭に付く記号です。
+const SUBTITLE_PREFIXES: &[char] = &['―', '〜'];
+
 /// タイトル、著者、【テキスト中に現れる記号について】といったファイルの先頭に記述される特別な情報を解析し、AozoraMetaに纏めます。
 ///
+/// 見出しは最初の空行までとし、青空文庫の凡例に従って次の順に記述されているものとみなします。
+/// - 作品名
+/// - 副題（省略可）
+/// - 原題（省略可）。ラテン文字で書かれた行を原題とみなします。
+/// - 著者名
+/// - 翻訳者名（`〇〇訳`）、編者名（`〇〇編`）（省略可）
+///
+/// 見出しの後に空行を挟んで【テキスト中に現れる記号について】が続く場合は、空行までをすべて見出しとみなします。
+/// そうでない場合は、本文の書き出しを著者と取り違えないよう、3行目以降は、それより後ろに翻訳者名か編者名の行がある場合と、
+/// 直前の行が原題か`――`などで始まる副題である場合に限って見出しとみなします。
+///
 /// パース成功後、`input` はメタデータ部分が消費された本文の先頭を指します。
 pub fn parse_meta<'s>(input: &mut &'s str) -> Result<AozoraMeta<'s>, MetaError> {
-    // タイトルをパース
-    let title: &str = (take_until::<_, _, ContextError>(1.., "\n"), '\n')
-        .map(|(s, _): (&str, char)| s.trim())
-        .parse_next(input)
-        .ok()
-        .ok_or(MetaError::NoTitleFound)?;
-    // 著者をパース
-    let author: &str = (take_until::<_, _, ContextError>(1.., "\n"), '\n')
-        .map(|(s, _): (&str, char)| s.trim())
+    let start = *input;
+    let lines = header_lines(input);
+    let Some(&(title, _)) = lines.first() else {
+        return Err(MetaError::NoTitleFound);
+    };
+    if lines.len() < 2 {
+        return Err(MetaError::NoAuthorFound);
+    }
+    let (_, rest) = lines[lines.len() - 1];
+    let span = |line: &str| {
+        let offset = line.as_ptr() as usize - start.as_ptr() as usize;
+        offset..offset + line.len()
+    };
+    let mut names: Vec<&str> = lines[1..].iter().map(|(l, _)| *l).collect();
+
+    // 著者の後に続く翻訳者、編者を末尾から読む
+    let mut translator = None;
+    let mut editor = None;
+    while names.len() > 1 {
+        let last = names[names.len() - 1];
+        if let Some(name) = strip_role(last, TRANSLATOR_SUFFIXES) {
+            translator.get_or_insert((name, last));
+        } else if let Some(name) = strip_role(last, EDITOR_SUFFIXES) {
+            editor.get_or_insert((name, last));
+        } else {
+            break;
+        }
+        names.pop();
+    }
+    let author = names.pop().ok_or(MetaError::NoAuthorFound)?;
+
+    // 作品名と著者名の間は副題か原題
+    let mut subtitle = None;
+    let mut original_title = None;
+    for line in names {
+        if is_latin(line) {
+            original_title.get_or_insert(line);
+        } else {
+            subtitle.get_or_insert(line);
+        }
+    }
+    let spans = MetaSpans {
+        title: span(title),
+        author: span(author),
+        subtitle: subtitle.map(span),
+        original_title: original_title.map(span),
+        translator: translator.map(|(_, line)| span(line)),
+        editor: editor.map(|(_, line)| span(line)),
+    };
+
+    // 【テキスト中に現れる記号について】をパース。見出しとの間に空行以外があれば読み飛ばさない
+    *input = rest;
+    let after_blank = rest.trim_start();
+    if after_blank.starts_with(ABOUT_SYMBOL) {
+        *input = after_blank;
+    }
+    let block_offset = start.len() - input.len();
+    let symbols = delimited(ABOUT_SYMBOL, take_until(0.., ABOUT_SYMBOL), ABOUT_SYMBOL)
         .parse_next(input)
-        .ok()
-        .ok_or(MetaError::NoAuthorFound)?;
-    // 【テキスト中に現れる記号について】をパース
-    let about_symbol = "-------------------------------------------------------";
-    let _: Result<(), _> = take_until::<_, _, ContextError>(0.., about_symbol)
-        .void()
-        .parse_next(input);
-    let _: Result<(), ContextError> =
-        delimited(about_symbol, take_until(0.., about_symbol), about_symbol)
-            .void()
-            .parse_next(input);
-    Ok(AozoraMeta { title, author })
+        .map(|block: &str| parse_symbol_block(block, block_offset + ABOUT_SYMBOL.len()))
+        .unwrap_or_else(|_: ContextError| Vec::new());
+    Ok(AozoraMeta {
+        title,
+        author,
+        subtitle,
+        original_title,
+        translator: translator.map(|(name, _)| name),
+        editor: editor.map(|(name, _)| name),
+        symbols,
+        spans,
+        ..Default::default()
+    })
+}
+
+/// 最初の空行か区切り線までの各行と、その行より後ろの残りを返します。
+///
+/// 空行の後に【テキスト中に現れる記号について】が続く場合は、それまでの行をすべて見出しとみなします。
+/// それ以外の場合は、本文を見出しと取り違えないよう、3行目以降は[`is_header_continuation`]を満たす行までを返します。
+/// 空行か区切り線で終わらない場合や、3行目以降に注記などの記号を含む行がある場合は、先頭2行のみを返します。
+fn header_lines(input: &str) -> Vec<(&str, &str)> {
+    let mut lines = Vec::new();
+    let mut rest = input;
+    let mut terminated = false;
+    while let Some((line, next)) = rest.split_once('\n') {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(ABOUT_SYMBOL) {
+            terminated = true;
+            break;
+        }
+        if lines.len() >= MAX_HEADER_LINES {
+            break;
+        }
+        lines.push((line, next));
+        rest = next;
+    }
+    if terminated && rest.trim_start().starts_with(ABOUT_SYMBOL) {
+        return lines;
+    }
+
+    // 注記などの記号は作品名と著者名にも現れうるので、3行目以降に限って本文の始まりとみなす
+    let plain = lines
+        .iter()
+        .skip(2)
+        .position(|(line, _)| line.contains(['［', '《', '｜', '※']))
+        .map_or(lines.len(), |i| i + 2);
+    let len = if terminated && plain == lines.len() {
+        (2..lines.len())
+            .rev()
+            .find(|&i| is_header_continuation(&lines, i))
+            .map_or(2, |i| i + 1)
+    } else {
+        2
+    };
+    lines.truncate(len);
+    lines
+}
+
+/// `i`行目（0始まり）までを見出しとみなせるかを返します。
+///
+/// 翻訳者名か編者名の行であれば、そこまでの行は著者名や副題とみなせます。
+/// 直前の行が原題か、`――`などで始まる副題であれば、その行は後に続く著者名とみなせます。
+fn is_header_continuation(lines: &[(&str, &str)], i: usize) -> bool {
+    let (line, _) = lines[i];
+    let (previous, _) = lines[i - 1];
+    strip_role(line, TRANSLATOR_SUFFIXES).is_some()
+        || strip_role(line, EDITOR_SUFFIXES).is_some()
+        || is_latin(previous)
+        || previous.starts_with(SUBTITLE_PREFIXES)
+}
+
+/// `line`が`suffixes`のいずれかで終わっていれば、それを取り除いた名前を返します。
+fn strip_role<'s>(line: &'s str, suffixes: &[&str]) -> Option<&'s str> {
+    suffixes
+        .iter()
+        .find_map(|s| line.strip_suffix(s))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// 文字がすべてラテン文字や数字、記号で、ラテン文字を含むかを返します。
+fn is_latin(line: &str) -> bool {
+    line.chars().any(|c| c.is_ascii_alphabetic())
+        && line
+            .chars()
+            .all(|c| c.is_ascii() || ('\u{00A0}'..='\u{024F}').contains(&c) || c == '’')
 }
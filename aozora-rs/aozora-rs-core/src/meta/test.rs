@@ -0,0 +1,122 @@
+use crate::{MetaSpans, parse_meta};
+
+#[test]
+fn simple_header_test() {
+    let mut input = "吾輩は猫である\n夏目漱石\n\n本文\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!((meta.title, meta.author), ("吾輩は猫である", "夏目漱石"));
+    assert_eq!(meta.subtitle, None);
+    assert_eq!(input, "\n本文\n");
+}
+
+#[test]
+fn translated_header_test() {
+    let mut input = "最後の一葉\nThe Last Leaf\nオー・ヘンリー\n結城浩訳\n\n本文\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!(meta.title, "最後の一葉");
+    assert_eq!(meta.original_title, Some("The Last Leaf"));
+    assert_eq!(meta.author, "オー・ヘンリー");
+    assert_eq!(meta.translator, Some("結城浩"));
+    assert_eq!(input, "\n本文\n");
+}
+
+#[test]
+fn subtitle_and_editor_test() {
+    let mut input = "日本の民話\n――東北篇\n佐々木喜善\n柳田国男編\n\n-------------------------------------------------------\n【テキスト中に現れる記号について】\n-------------------------------------------------------\n本文\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!(meta.subtitle, Some("――東北篇"));
+    assert_eq!(meta.author, "佐々木喜善");
+    assert_eq!(meta.editor, Some("柳田国男"));
+    assert_eq!(input, "\n本文\n");
+}
+
+#[test]
+fn no_blank_line_test() {
+    // 見出しの後に空行が無ければ、先頭2行のみを見出しとみなす
+    let mut input = "題\n著者\n一\n二\n三\n四\n五\n六\n七\n八\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!((meta.title, meta.author), ("題", "著者"));
+    assert_eq!(input, "一\n二\n三\n四\n五\n六\n七\n八\n");
+}
+
+#[test]
+fn annotated_line_is_not_header_test() {
+    let mut input = "題\n著者\n［＃大見出し］第一章［＃大見出し終わり］\n\n本文\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!((meta.title, meta.author), ("題", "著者"));
+    assert!(input.starts_with("［＃大見出し］"));
+}
+
+#[test]
+fn body_after_author_is_not_header_test() {
+    // 翻訳者名や編者名、原題が無ければ、3行目以降は本文とみなす
+    let mut input = "題\n著者\n本文の書き出し\n\n本文\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!((meta.title, meta.author), ("題", "著者"));
+    assert_eq!(meta.subtitle, None);
+    assert_eq!(input, "本文の書き出し\n\n本文\n");
+
+    let mut input = "題\nThe Title\n著者\n\n本文\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!(meta.original_title, Some("The Title"));
+    assert_eq!(meta.author, "著者");
+}
+
+#[test]
+fn subtitle_before_symbol_block_test() {
+    let mut input = "銀河鉄道の夜\n――初期形\n宮沢賢治\n\n-------------------------------------------------------\n【テキスト中に現れる記号について】\n\n《》：ルビ\n-------------------------------------------------------\n本文\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!(meta.title, "銀河鉄道の夜");
+    assert_eq!(meta.subtitle, Some("――初期形"));
+    assert_eq!(meta.author, "宮沢賢治");
+    assert_eq!(meta.symbols.len(), 1);
+    assert_eq!(input, "\n本文\n");
+}
+
+#[test]
+fn dash_subtitle_test() {
+    // 記号の説明が無くても、――で始まる副題の次の行は著者名とみなす
+    let mut input = "銀河鉄道の夜\n――初期形\n宮沢賢治\n\n本文\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!(meta.subtitle, Some("――初期形"));
+    assert_eq!(meta.author, "宮沢賢治");
+    assert_eq!(input, "\n本文\n");
+}
+
+#[test]
+fn body_is_not_skipped_test() {
+    // 見出しと記号の説明の間に本文があれば、記号の説明まで読み飛ばさない
+    let mut input = "題\n著者\n\n本文の書き出し\n\n-------------------------------------------------------\n【テキスト中に現れる記号について】\n-------------------------------------------------------\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!((meta.title, meta.author), ("題", "著者"));
+    assert!(meta.symbols.is_empty());
+    assert!(input.starts_with("\n本文の書き出し\n"));
+}
+
+#[test]
+fn gaiji_in_author_test() {
+    let mut input = "題\n※［＃「王＋干」、第3水準1-87-83］著者\n\n本文\n";
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!(meta.author, "※［＃「王＋干」、第3水準1-87-83］著者");
+    assert_eq!(input, "\n本文\n");
+}
+
+#[test]
+fn spans_test() {
+    let text = "最後の一葉\nThe Last Leaf\n　オー・ヘンリー\n結城浩訳\n\n本文\n";
+    let mut input = text;
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!(
+        meta.spans,
+        MetaSpans {
+            title: 0..15,
+            author: 33..54,
+            subtitle: None,
+            original_title: Some(16..29),
+            translator: Some(55..67),
+            editor: None,
+        }
+    );
+    assert_eq!(&text[meta.spans.author.clone()], "オー・ヘンリー");
+    assert_eq!(&text[meta.spans.translator.unwrap()], "結城浩訳");
+}
@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod test;
+
+/// Zip内のパスや図の注記に書かれたパスを、比較できる形に正規化します。
+///
+/// 区切り文字を`/`に統一し、先頭の`./`や`/`、途中の`.`と`..`を解決します。
+/// 大文字と小文字は区別したまま返すので、比較の際は[`same_path`]を用いてください。
+pub fn normalize_path(path: &str) -> String {
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => (),
+            ".." => {
+                components.pop();
+            }
+            c => components.push(c),
+        }
+    }
+    components.join("/")
+}
+
+/// `dir`からの相対パス`path`を結合し、正規化して返します。
+pub fn join_path(dir: &str, path: &str) -> String {
+    normalize_path(&format!("{}/{}", dir, path))
+}
+
+/// `path`が置かれたディレクトリを返します。最上位にあれば空文字列を返します。
+pub fn parent_dir(path: &str) -> String {
+    let normalized = normalize_path(path);
+    match normalized.rsplit_once('/') {
+        Some((dir, _)) => dir.to_string(),
+        None => String::new(),
+    }
+}
+
+/// 正規化した2つのパスが、大文字と小文字の違いを除いて一致するかを返します。
+pub fn same_path(a: &str, b: &str) -> bool {
+    normalize_path(a).to_lowercase() == normalize_path(b).to_lowercase()
+}
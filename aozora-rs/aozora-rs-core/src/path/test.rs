@@ -0,0 +1,11 @@
+use crate::{join_path, normalize_path, parent_dir, same_path};
+
+#[test]
+fn normalize_test() {
+    assert_eq!(normalize_path("./fig/a.png"), "fig/a.png");
+    assert_eq!(normalize_path("work\\fig\\..\\a.png"), "work/a.png");
+    assert_eq!(join_path("work/files", "../fig/a.png"), "work/fig/a.png");
+    assert_eq!(parent_dir("work/files/main.txt"), "work/files");
+    assert_eq!(parent_dir("main.txt"), "");
+    assert!(same_path("Work/Fig/A.PNG", "./work/fig/a.png"));
+}
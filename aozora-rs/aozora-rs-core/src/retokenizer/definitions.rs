@@ -9,6 +9,8 @@ pub use pages::Page;
 pub enum Retokenized<'s> {
     /// 切り出したテキストに対応します。
     Text(&'s str),
+    /// 外字注記に対応します。
+    Gaiji(Gaiji<'s>),
     /// 漢文における訓点に対応します。
     Kunten(&'s str),
     /// 漢文における送り仮名に対応します。
@@ -24,25 +26,68 @@ pub enum Retokenized<'s> {
 }
 
 /// 再トークン化時に発生しうるエラーの直和です。
-#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum RetokenizeError {
     /// トークンを閉じろという命令が出た際、閉じるトークンがなければこのエラーが生じます。
-    #[default]
-    InvalidEndOfToken,
+    ///
+    /// 閉じられなかった、または開始されなかったトークンの範囲を持ちます。
+    InvalidEndOfToken(Span),
     /// スコープを閉じろという命令が出た際、閉じるスコープがなければこのエラーが生じます。
-    InvalidEndOfScope,
+    ///
+    /// 問題のスコープの範囲と、その装飾の名前（[`Deco`]を表示した文字列）を持ちます。
+    InvalidEndOfScope(Span, String),
+}
+
+impl Default for RetokenizeError {
+    fn default() -> Self {
+        RetokenizeError::InvalidEndOfToken(0..0)
+    }
 }
 
 impl std::fmt::Display for RetokenizeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::InvalidEndOfScope => "スコープの終了地点が不正です。これは内部的なエラーです",
-                Self::InvalidEndOfToken => "トークンの終了地点が不正です。これは内部的なエラーです",
+        match self {
+            Self::InvalidEndOfScope(_, d) => write!(
+                f,
+                "{}のスコープの終了地点が不正です。これは内部的なエラーです",
+                d
+            ),
+            Self::InvalidEndOfToken(_) => {
+                write!(f, "トークンの終了地点が不正です。これは内部的なエラーです")
             }
-        )
+        }
+    }
+}
+
+impl RetokenizeError {
+    /// エラーが発生した位置を返します。
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::InvalidEndOfToken(s) => s,
+            Self::InvalidEndOfScope(s, _) => s,
+        }
+    }
+
+    /// エラーに対応する診断コードを返します。
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidEndOfToken(_) => codes::INVALID_END_OF_TOKEN,
+            Self::InvalidEndOfScope(..) => codes::INVALID_END_OF_SCOPE,
+        }
+    }
+
+    /// 診断に変換します。
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let args = match self {
+            Self::InvalidEndOfToken(_) => Vec::new(),
+            Self::InvalidEndOfScope(_, d) => vec![d.clone()],
+        };
+        Diagnostic::new(self.code(), Severity::Error, args).with_span(self.span().clone())
+    }
+
+    /// `original`を受け取り、人間に親切な形でエラーを表示します。
+    pub fn display(&self, original: &str) -> String {
+        self.to_diagnostic().display(original)
     }
 }
 
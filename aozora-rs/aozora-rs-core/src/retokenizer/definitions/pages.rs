@@ -32,30 +32,36 @@ impl<'s> Page<'s> {
         // 蓄積用
         let mut err = Vec::new();
 
-        // 閉じられていないトークン
+        // 閉じられていないトークンと、その元の範囲
         let mut unclosed_token = (Option::None, 0);
-        // 閉じられていないタグ
-        let mut unclosed_decos: Vec<Deco<'s>> = Vec::new();
+        let mut unclosed_token_span = 0..0;
+        // 閉じられていないタグと、その元の範囲
+        let mut unclosed_decos: Vec<(Deco<'s>, Span)> = Vec::new();
 
         fn warn_at_end<'s>(
             err: &mut Vec<RetokenizeError>,
             ut: (Option<Element>, usize),
-            ud: Vec<Deco<'s>>,
+            ut_span: Span,
+            ud: Vec<(Deco<'s>, Span)>,
         ) {
             if let (Some(_), _) = ut {
-                err.push(RetokenizeError::InvalidEndOfToken);
+                err.push(RetokenizeError::InvalidEndOfToken(ut_span));
             }
-            err.extend([RetokenizeError::InvalidEndOfScope].repeat(ud.len()));
+            err.extend(
+                ud.into_iter()
+                    .map(|(deco, span)| RetokenizeError::InvalidEndOfScope(span, deco.to_string())),
+            );
         }
 
         for (i, event) in events.by_ref() {
             match event {
                 // 平坦トークン開始
-                RetokenizeEvent::FlatTBegin(f) => {
+                RetokenizeEvent::FlatTBegin(f, span) => {
                     unclosed_token = (Some(f), i);
+                    unclosed_token_span = span;
                 }
                 // 平坦トークン終了
-                RetokenizeEvent::FlatTEnd => match unclosed_token.0 {
+                RetokenizeEvent::FlatTEnd(span) => match unclosed_token.0 {
                     Some(t) => {
                         // 閉じる
                         self.push(t.into());
@@ -63,10 +69,10 @@ impl<'s> Page<'s> {
                     }
                     None => err.push(
                         // 開始されていないものを閉じたらエラーを蓄積
-                        RetokenizeError::InvalidEndOfToken,
+                        RetokenizeError::InvalidEndOfToken(span),
                     ),
                 },
-                RetokenizeEvent::DecoBegin(d) => {
+                RetokenizeEvent::DecoBegin(d, span) => {
                     if let (Some(t), unclosed_until) = unclosed_token {
                         // 閉じられていないトークンがあれば一次終了して装飾を開始
                         // トークンを現在位置で分割
@@ -74,17 +80,17 @@ impl<'s> Page<'s> {
                         // 分割以前を確定、開始タグを挿入、分割以後で未確定トークンを再開
                         self.push(confirmed.into());
                         self.push(Retokenized::DecoBegin(d.clone()));
-                        unclosed_decos.push(d);
+                        unclosed_decos.push((d, span));
                         unclosed_token = (unclosed, i);
                     } else {
                         // 閉じられていないトークンが無かったときは単純に開始タグを挿入
                         self.push(Retokenized::DecoBegin(d.clone()));
-                        unclosed_decos.push(d);
+                        unclosed_decos.push((d, span));
                     }
                 }
-                RetokenizeEvent::DecoEnd => {
+                RetokenizeEvent::DecoEnd(ending, span) => {
                     // 装飾を終了
-                    let popped_deco = unclosed_decos.pop();
+                    let popped_deco = unclosed_decos.pop().map(|(d, _)| d);
 
                     if let (Some(t), unclosed_until) = unclosed_token {
                         // 閉じられていないトークンがあったら
@@ -97,7 +103,7 @@ impl<'s> Page<'s> {
                             unclosed_token = (unclosed, i);
                         } else {
                             // 開始されなかったスコープを終了しようとしたらエラー
-                            err.push(RetokenizeError::InvalidEndOfScope);
+                            err.push(RetokenizeError::InvalidEndOfScope(span, ending.to_string()));
                             unclosed_token = (Some(t), unclosed_until);
                         }
                     } else {
@@ -107,13 +113,18 @@ impl<'s> Page<'s> {
                             self.push(Retokenized::DecoEnd(d));
                         } else {
                             // 閉じられていない装飾もなければエラー
-                            err.push(RetokenizeError::InvalidEndOfScope);
+                            err.push(RetokenizeError::InvalidEndOfScope(span, ending.to_string()));
                         }
                     }
                 }
                 // 改ページがあれば終了
                 RetokenizeEvent::PageBreak => {
-                    warn_at_end(&mut err, unclosed_token, unclosed_decos);
+                    warn_at_end(
+                        &mut err,
+                        unclosed_token,
+                        unclosed_token_span,
+                        unclosed_decos,
+                    );
                     return err;
                 }
                 // ページ定義があれば自身をmutate
@@ -124,7 +135,12 @@ impl<'s> Page<'s> {
                 },
             }
         }
-        warn_at_end(&mut err, unclosed_token, unclosed_decos);
+        warn_at_end(
+            &mut err,
+            unclosed_token,
+            unclosed_token_span,
+            unclosed_decos,
+        );
         err
     }
 }
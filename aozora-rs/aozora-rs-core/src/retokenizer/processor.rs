@@ -1,17 +1,18 @@
 use std::cmp::Ordering;
 
 use crate::{
-    Deco, ExpAcc, Expression, Page, PageBreak, PageDef, RetokenizeError, ScopeAcc,
+    Deco, ExpAcc, Expression, Page, PageBreak, PageDef, RetokenizeError, ScopeAcc, Span,
     scopenizer::Element,
 };
 
-#[derive(Default, Debug)]
+/// 再トークン化の過程で処理するイベントです。
+/// 開始と終了のイベントは、エラーの報告に用いるため元の範囲を保持します。
+#[derive(Debug)]
 pub enum RetokenizeEvent<'s> {
-    FlatTBegin(Element<'s>),
-    #[default]
-    FlatTEnd,
-    DecoBegin(Deco<'s>),
-    DecoEnd,
+    FlatTBegin(Element<'s>, Span),
+    FlatTEnd(Span),
+    DecoBegin(Deco<'s>, Span),
+    DecoEnd(Deco<'s>, Span),
     PageDef(PageDef),
     PageBreak,
 }
@@ -21,14 +22,17 @@ type Events<'s> = Vec<(usize, RetokenizeEvent<'s>)>;
 pub fn extract_events<'s>(expressions: ExpAcc<'s>, scopenized: ScopeAcc<'s>) -> Events<'s> {
     let mut events = Vec::new();
     for s in scopenized.into_iter() {
-        events.push((s.span.start, RetokenizeEvent::DecoBegin(s.deco)));
-        events.push((s.span.end, RetokenizeEvent::DecoEnd));
+        events.push((
+            s.span.start,
+            RetokenizeEvent::DecoBegin(s.deco.clone(), s.span.clone()),
+        ));
+        events.push((s.span.end, RetokenizeEvent::DecoEnd(s.deco, s.span)));
     }
     for (expression, scope) in expressions {
         match expression {
             Expression::Element(e) => {
-                events.push((scope.start, RetokenizeEvent::FlatTBegin(e)));
-                events.push((scope.end, RetokenizeEvent::FlatTEnd));
+                events.push((scope.start, RetokenizeEvent::FlatTBegin(e, scope.clone())));
+                events.push((scope.end, RetokenizeEvent::FlatTEnd(scope)));
             }
             Expression::PageBreak(b) => {
                 events.push((scope.start, RetokenizeEvent::PageBreak));
@@ -60,10 +64,10 @@ pub fn extract_events<'s>(expressions: ExpAcc<'s>, scopenized: ScopeAcc<'s>) ->
         // インデックスが同じ場合はイベントの優先度で比較
         fn priority(e: &RetokenizeEvent) -> u8 {
             match e {
-                RetokenizeEvent::FlatTEnd => 0,
-                RetokenizeEvent::DecoEnd => 1,
-                RetokenizeEvent::DecoBegin(_) => 2,
-                RetokenizeEvent::FlatTBegin(_) => 3,
+                RetokenizeEvent::FlatTEnd(_) => 0,
+                RetokenizeEvent::DecoEnd(..) => 1,
+                RetokenizeEvent::DecoBegin(..) => 2,
+                RetokenizeEvent::FlatTBegin(..) => 3,
                 RetokenizeEvent::PageDef(_) => 4,
                 RetokenizeEvent::PageBreak => 5,
             }
@@ -87,7 +91,7 @@ pub fn retokenize<'s>(
 
     while events.peek().is_some() {
         let mut page = Page::default();
-        errors.extend(page.retokenize(&mut events).into_iter());
+        errors.extend(page.retokenize(&mut events));
         pages.push(page);
     }
 
@@ -1,6 +1,6 @@
 use winnow::LocatingSlice;
 
-use crate::{Deco, Retokenized, retokenize, scopenize, tokenize};
+use crate::{Deco, RetokenizeError, Retokenized, retokenize, scopenize, tokenize};
 
 #[test]
 fn kyusoku() {
@@ -23,3 +23,22 @@ fn kyusoku() {
         ]
     )
 }
+
+#[test]
+fn scope_across_page_break() {
+    let input = "［＃ここから２字下げ］\n吾輩\n［＃改ページ］\n猫\n［＃ここで字下げ終わり］";
+
+    let tokenized = tokenize(&mut LocatingSlice::new(input)).unwrap();
+    let ((scope, exps), _) = scopenize(tokenized).into_tuple();
+    let (_, rerr) = retokenize(exps, scope);
+
+    // 改ページで閉じられなかったスコープと、次のページで開始されていないスコープの終了
+    assert_eq!(
+        rerr,
+        vec![
+            RetokenizeError::InvalidEndOfScope(33..67, Deco::Indent(2).to_string()),
+            RetokenizeError::InvalidEndOfScope(33..67, Deco::Indent(2).to_string()),
+        ]
+    );
+    assert!(rerr[0].display(input).contains("2字下げ"));
+}
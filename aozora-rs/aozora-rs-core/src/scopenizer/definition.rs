@@ -42,6 +42,8 @@ pub enum Expression<'s> {
 pub enum Element<'s> {
     /// 切り出したテキストに対応します。
     Text(&'s str),
+    /// 外字注記に対応します。
+    Gaiji(Gaiji<'s>),
     /// 改行に対応します。
     Br,
     /// 漢文における訓点に対応します。
@@ -72,7 +74,7 @@ impl<'s> Element<'s> {
                 Some(Element::Text(&t[at..t.len()])),
             )
         } else {
-            return (self, None);
+            (self, None)
         }
     }
 }
@@ -85,6 +87,7 @@ impl<'s> From<Element<'s>> for Retokenized<'s> {
             Element::Kunten(k) => Retokenized::Kunten(k),
             Element::Okurigana(o) => Retokenized::Okurigana(o),
             Element::Text(t) => Retokenized::Text(t),
+            Element::Gaiji(g) => Retokenized::Gaiji(g),
         }
     }
 }
@@ -1,3 +1,5 @@
+use winnow::LocatingSlice;
+
 use crate::*;
 
 /// [`scopenize`]の過程で発生するエラーの直和です。
@@ -22,26 +24,214 @@ impl Default for ScopenizeError {
 }
 
 impl ScopenizeError {
+    /// エラーが発生した位置を返します。
+    pub fn span(&self) -> &Span {
+        match self {
+            ScopenizeError::BackRefFailed(s) => s,
+            ScopenizeError::CrossingNote(s) => s,
+            ScopenizeError::InvalidRubyDelimiterUsage(s) => s,
+            ScopenizeError::IsolatedEndNote(s) => s,
+            ScopenizeError::UnclosedInlineNote(s) => s,
+        }
+    }
+
+    /// エラーに対応する診断コードを返します。
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnclosedInlineNote(_) => codes::UNCLOSED_INLINE_NOTE,
+            Self::BackRefFailed(_) => codes::BACK_REF_FAILED,
+            Self::InvalidRubyDelimiterUsage(_) => codes::INVALID_RUBY_DELIMITER_USAGE,
+            Self::CrossingNote(_) => codes::CROSSING_NOTE,
+            Self::IsolatedEndNote(_) => codes::ISOLATED_END_NOTE,
+        }
+    }
+
+    /// エラーの重大度を返します。
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::BackRefFailed(_) | Self::InvalidRubyDelimiterUsage(_) => Severity::Error,
+            Self::UnclosedInlineNote(_) | Self::CrossingNote(_) | Self::IsolatedEndNote(_) => {
+                Severity::Warning
+            }
+        }
+    }
+
+    /// `original`を参照して、関連する注記への注釈と修正案を持つ診断に変換します。
+    ///
+    /// `original`はエラーの範囲が指し示すテキスト全体である必要があります。
+    pub fn to_diagnostic(&self, original: &str) -> Diagnostic {
+        let span = self.span();
+        let diagnostic =
+            Diagnostic::new(self.code(), self.severity(), Vec::new()).with_span(span.clone());
+        match self {
+            Self::UnclosedInlineNote(_) => {
+                let diagnostic = match annotation_starting_at(original, span.start) {
+                    Some(begin) => {
+                        diagnostic.with_label(begin, Message::plain(messages::UNCLOSED_BEGIN))
+                    }
+                    None => diagnostic,
+                };
+                diagnostic.with_fix(fix_unclosed(original, span))
+            }
+            Self::CrossingNote(_) => {
+                let diagnostic = match annotation_ending_at(original, span.start) {
+                    Some(begin) => {
+                        diagnostic.with_label(begin, Message::plain(messages::CROSSING_BEGIN))
+                    }
+                    None => diagnostic,
+                };
+                match annotation_starting_at(original, span.end) {
+                    Some(end) => diagnostic.with_label(end, Message::plain(messages::CROSSING_END)),
+                    None => diagnostic,
+                }
+            }
+            Self::IsolatedEndNote(_) => diagnostic.with_fix(Some(Fix {
+                title: Message::new(
+                    messages::DELETE_NOTE,
+                    vec![original[span.clone()].to_string()],
+                ),
+                edits: vec![Edit {
+                    span: span.clone(),
+                    replacement: String::new(),
+                }],
+            })),
+            Self::BackRefFailed(_) if original[span.clone()].starts_with('《') => {
+                diagnostic.with_fix(fix_ruby(original, span))
+            }
+            Self::BackRefFailed(_) => diagnostic.with_fix(fix_backref(original, span)),
+            Self::InvalidRubyDelimiterUsage(_) => diagnostic,
+        }
+    }
+
     /// `original`を受け取り、人間に親切な形でエラーを表示します。
     pub fn display(&self, original: &str) -> String {
-        display_error_with_decolation(
-            original,
-            match self {
-                ScopenizeError::BackRefFailed(s) => s,
-                ScopenizeError::CrossingNote(s) => s,
-                ScopenizeError::InvalidRubyDelimiterUsage(s) => s,
-                ScopenizeError::IsolatedEndNote(s) => s,
-                ScopenizeError::UnclosedInlineNote(s) => s,
-            }
-            .clone(),
-            "ScopenizeError",
-            match self {
-                Self::BackRefFailed(_) => "前方参照に失敗しました",
-                Self::CrossingNote(_) => "注記が交差しています",
-                ScopenizeError::InvalidRubyDelimiterUsage(_) => "ルビの使用方法が不正です",
-                ScopenizeError::IsolatedEndNote(_) => "開始注記のない終了注記が存在します",
-                ScopenizeError::UnclosedInlineNote(_) => "行内注記が閉じられていません",
+        self.to_diagnostic(original).display(original)
+    }
+}
+
+/// 注記の中身（`［＃`と`］`の間）が挟み込み型注記の開始または終了として解釈できるか
+fn is_sandwiched(note: &str, begin: bool) -> bool {
+    let text = format!("［＃{}］", note);
+    let Ok(tokens) = tokenize(&mut LocatingSlice::new(text.as_str())) else {
+        return false;
+    };
+    match tokens.as_slice() {
+        [token] => match &token.kind {
+            AozoraTokenKind::Annotation(Annotation::Sandwiched(Sandwiched::Begin(_))) => begin,
+            AozoraTokenKind::Annotation(Annotation::Sandwiched(Sandwiched::End(_))) => !begin,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// 挟み込み型注記の開始側の中身から、対応する終了側の中身を作る
+fn closing_note(begin: &str) -> Option<String> {
+    let end = if begin.ends_with("段階小さな文字") {
+        "小さな文字終わり".to_string()
+    } else if begin.ends_with("段階大きな文字") {
+        "大きな文字終わり".to_string()
+    } else {
+        format!("{}終わり", begin)
+    };
+    is_sandwiched(&end, false).then_some(end)
+}
+
+fn note_content(note: &str) -> Option<&str> {
+    note.strip_prefix("［＃")?.strip_suffix("］")
+}
+
+fn insert(offset: usize, text: String) -> Edit {
+    Edit {
+        span: offset..offset,
+        replacement: text,
+    }
+}
+
+fn line_start(original: &str, offset: usize) -> usize {
+    original[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0)
+}
+
+/// 閉じられていない挟み込み型注記を行末で閉じる
+fn fix_unclosed(original: &str, span: &Span) -> Option<Fix> {
+    let begin = annotation_starting_at(original, span.start)?;
+    let end = closing_note(note_content(&original[begin])?)?;
+    // エラーの範囲は行末の改行までを含む
+    let line_end = span.end - original[..span.end].ends_with('\n') as usize;
+    let note = format!("［＃{}］", end);
+    Some(Fix {
+        title: Message::new(messages::INSERT_NOTE, vec![note.clone()]),
+        edits: vec![insert(line_end, note)],
+    })
+}
+
+/// 前方参照に失敗した注記を、同じ行にある対象文字列を挟み込む注記に書き換える
+fn fix_backref(original: &str, span: &Span) -> Option<Fix> {
+    let content = note_content(&original[span.clone()])?;
+    let (target, rest) = content.strip_prefix('「')?.split_once('」')?;
+    let kind = rest
+        .strip_prefix('は')
+        .or_else(|| rest.strip_prefix('に'))?;
+    if target.is_empty() || !is_sandwiched(kind, true) {
+        return None;
+    }
+    let end = closing_note(kind)?;
+
+    let line_start = line_start(original, span.start);
+    let found = line_start + original[line_start..span.start].rfind(target)?;
+    Some(Fix {
+        title: Message::new(
+            messages::REWRITE_AS_SANDWICHED,
+            vec![kind.to_string(), end.to_string()],
+        ),
+        edits: vec![
+            insert(found, format!("［＃{}］", kind)),
+            insert(found + target.len(), format!("［＃{}］", end)),
+            Edit {
+                span: span.clone(),
+                replacement: String::new(),
             },
-        )
+        ],
+    })
+}
+
+/// ルビを付けたい範囲の先頭を推測する。末尾の文字と同じ字種が続く範囲を親文字とみなす
+fn ruby_base_start(text: &str) -> usize {
+    fn class(c: char) -> u8 {
+        match c {
+            'ぁ'..='ゖ' | 'ゝ' | 'ゞ' => 0,
+            'ァ'..='ヺ' | 'ー' | 'ヽ' | 'ヾ' => 1,
+            c if c.is_whitespace() || "、。，．・「」『』（）〔〕！？".contains(c) => {
+                2
+            }
+            _ => 3,
+        }
+    }
+    let Some(last) = text.chars().next_back().map(class).filter(|&c| c != 2) else {
+        return text.len();
+    };
+    text.char_indices()
+        .rev()
+        .take_while(|(_, c)| class(*c) == last)
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(text.len())
+}
+
+/// 漢字以外の文字列に付いたルビの前にルビ区切りを挿入する
+fn fix_ruby(original: &str, span: &Span) -> Option<Fix> {
+    // ルビの直前にあるテキストの範囲を、トークナイザと同じ区切り文字で切り出す
+    let before = &original[..span.start];
+    let base_start = before
+        .rfind(['｜', '\n', '］', '》'])
+        .map(|i| i + before[i..].chars().next().unwrap().len_utf8())
+        .unwrap_or(0);
+    let start = base_start + ruby_base_start(&before[base_start..]);
+    if start == span.start {
+        return None;
     }
+    Some(Fix {
+        title: Message::plain(messages::INSERT_RUBY_DELIMITER),
+        edits: vec![insert(start, "｜".to_string())],
+    })
 }
@@ -48,22 +48,50 @@ pub fn scopenize<'s>(
                 }
                 flatten.push((Element::Text(t).into(), token.span.clone()));
             }
+            AozoraTokenKind::Gaiji(g) => {
+                // 外字の直後のルビは外字を親文字とする
+                if let Some(Tokenized {
+                    kind: AozoraTokenKind::Ruby(ruby),
+                    ..
+                }) = peekable.peek()
+                {
+                    scopes.push(Scope {
+                        deco: Deco::Ruby(ruby),
+                        span: token.span.clone(),
+                    });
+                    peekable.next();
+                }
+                flatten.push((Element::Gaiji(g).into(), token.span.clone()));
+            }
             AozoraTokenKind::RubyDelimiter => {
-                // ルビ区切りが出たら次のトークンがテキスト、次の次のトークンがルビであることを期待する
-                if let (Some(t), Some(r)) = (peekable.next(), peekable.next())
-                    && let (AozoraTokenKind::Text(text), AozoraTokenKind::Ruby(ruby)) =
-                        (t.kind, r.kind)
+                // ルビ区切りが出たら、テキストか外字が1つ以上続いたあとにルビが来ることを期待する
+                let mut base = Vec::new();
+                while let Some(t) = peekable.next_if(|t| {
+                    matches!(t.kind, AozoraTokenKind::Text(_) | AozoraTokenKind::Gaiji(_))
+                }) {
+                    base.push(t);
+                }
+                let ruby = peekable.next_if(|r| matches!(r.kind, AozoraTokenKind::Ruby(_)));
+                if let (Some(first), Some(last), Some(r)) = (base.first(), base.last(), ruby)
+                    && let AozoraTokenKind::Ruby(ruby) = r.kind
                 {
-                    flatten.push((Element::Text(text).into(), t.span.clone()));
                     scopes.push(Scope {
                         deco: Deco::Ruby(ruby),
-                        span: t.span,
+                        span: first.span.start..last.span.end,
                     });
                 } else {
                     // 修復は難しいので無視
-                    azc.acc_err(
-                        ScopenizeError::InvalidRubyDelimiterUsage(token.span.clone()),
-                    );
+                    azc.acc_err(ScopenizeError::InvalidRubyDelimiterUsage(
+                        token.span.clone(),
+                    ));
+                }
+                for t in base {
+                    let element = match t.kind {
+                        AozoraTokenKind::Gaiji(g) => Element::Gaiji(g),
+                        AozoraTokenKind::Text(text) => Element::Text(text),
+                        _ => unreachable!(),
+                    };
+                    flatten.push((element.into(), t.span));
                 }
             }
             AozoraTokenKind::Annotation(c) => match c {
@@ -92,3 +92,28 @@ fn multiline_test() {
         }
     );
 }
+
+#[test]
+fn ruby_on_gaiji_test() {
+    let (scope, err) = easy_scopenize("※［＃「木＋世」、第3水準1-85-56］《かじ》");
+
+    assert!(err.is_empty());
+    assert_eq!(
+        scope,
+        Scope {
+            deco: Deco::Ruby("かじ"),
+            span: 0..47
+        }
+    );
+
+    let (scope, err) = easy_scopenize("｜※［＃「木＋世」、第3水準1-85-56］の木《かじのき》");
+
+    assert!(err.is_empty());
+    assert_eq!(
+        scope,
+        Scope {
+            deco: Deco::Ruby("かじのき"),
+            span: 3..56
+        }
+    );
+}
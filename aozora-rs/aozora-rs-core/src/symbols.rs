@@ -0,0 +1,207 @@
+//! 【テキスト中に現れる記号について】を扱うためのモジュールです。
+//!
+//! 記号の説明を構造化して読み取るほか、本文で使われている記法が説明に宣言されているかを検査します。
+
+#[cfg(test)]
+mod test;
+
+use crate::{AozoraTokenKind, Diagnostic, Severity, Span, Tokenized, codes};
+
+/// 【テキスト中に現れる記号について】に記述された記号1件分です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolEntry<'s> {
+    /// 記号です。（例：`《》`）
+    pub symbol: &'s str,
+    /// 記号の説明です。字下げされた続きの行も1行ずつ含みます。
+    pub explanation: Vec<&'s str>,
+    /// `（例）`に続く用例です。
+    pub examples: Vec<&'s str>,
+    /// 記号の説明全体の位置です。
+    pub span: Span,
+}
+
+/// 区切り線に囲まれた【テキスト中に現れる記号について】を解析し、記号ごとに纏めます。
+///
+/// `offset`は`block`の先頭の位置で、各記号の`span`に加えられます。
+pub fn parse_symbol_block(block: &str, offset: usize) -> Vec<SymbolEntry<'_>> {
+    let mut entries: Vec<SymbolEntry> = Vec::new();
+    let mut position = offset;
+    for raw in block.split_inclusive('\n') {
+        let start = position;
+        position += raw.len();
+        let end = start + raw.trim_end().len();
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('-') || line.starts_with('【') {
+            continue;
+        }
+        if let Some(example) = line.strip_prefix("（例）") {
+            if let Some(entry) = entries.last_mut() {
+                entry.examples.push(example.trim());
+                entry.span.end = end;
+            }
+            continue;
+        }
+        // 字下げされた行は直前の記号の説明の続き
+        if raw.starts_with(['　', ' ', '\t']) {
+            if let Some(entry) = entries.last_mut() {
+                entry.explanation.push(line);
+                entry.span.end = end;
+            }
+            continue;
+        }
+        match line.split_once('：') {
+            Some((symbol, explanation)) if !symbol.is_empty() => entries.push(SymbolEntry {
+                symbol: symbol.trim(),
+                explanation: vec![explanation.trim()],
+                examples: Vec::new(),
+                span: start..end,
+            }),
+            _ => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.explanation.push(line);
+                    entry.span.end = end;
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// 【テキスト中に現れる記号について】で宣言されるべき記法の種類です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    /// ルビ（《》）です。
+    Ruby,
+    /// ルビの付く文字列の始まりを特定する記号（｜）です。
+    RubyDelimiter,
+    /// 入力者注（［＃］）です。
+    Annotation,
+    /// 外字注記（※）です。
+    Gaiji,
+    /// アクセント分解された欧文（〔〕）です。
+    Accent,
+}
+
+impl Notation {
+    /// 宣言に用いられる記号を返します。
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Ruby => "《》",
+            Self::RubyDelimiter => "｜",
+            Self::Annotation => "［＃］",
+            Self::Gaiji => "※",
+            Self::Accent => "〔〕",
+        }
+    }
+
+    /// 記法の名前を返します。
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ruby => "ルビ",
+            Self::RubyDelimiter => "ルビの開始位置の指定",
+            Self::Annotation => "入力者注",
+            Self::Gaiji => "外字注記",
+            Self::Accent => "アクセント分解",
+        }
+    }
+
+    /// トークンに対応する記法を返します。テキストや改行では`None`を返します。
+    pub fn of_token(kind: &AozoraTokenKind) -> Option<Self> {
+        match kind {
+            AozoraTokenKind::Ruby(_) => Some(Self::Ruby),
+            AozoraTokenKind::RubyDelimiter => Some(Self::RubyDelimiter),
+            AozoraTokenKind::Annotation(_) => Some(Self::Annotation),
+            AozoraTokenKind::Gaiji(_) => Some(Self::Gaiji),
+            AozoraTokenKind::Text(_) | AozoraTokenKind::Br => None,
+        }
+    }
+
+    /// `entries`の中でこの記法が宣言されているかを返します。
+    ///
+    /// 外字注記は専用の項目を持たず、入力者注の説明や用例の中で触れられることが多いので、
+    /// 説明や用例に`※`か`外字`が現れていれば宣言されているものとみなします。
+    pub fn is_declared_in(&self, entries: &[SymbolEntry]) -> bool {
+        entries.iter().any(|e| {
+            e.symbol.contains(self.symbol())
+                || (*self == Self::Gaiji
+                    && e.explanation
+                        .iter()
+                        .chain(&e.examples)
+                        .any(|l| l.contains('※') || l.contains("外字")))
+        })
+    }
+}
+
+/// 【テキスト中に現れる記号について】で宣言されている記法を列挙します。
+pub fn declared_notations(entries: &[SymbolEntry]) -> Vec<Notation> {
+    [
+        Notation::Ruby,
+        Notation::RubyDelimiter,
+        Notation::Annotation,
+        Notation::Gaiji,
+        Notation::Accent,
+    ]
+    .into_iter()
+    .filter(|n| n.is_declared_in(entries))
+    .collect()
+}
+
+/// テキストの中からアクセント分解された欧文（`〔Quid aliud est〕`のような、ASCIIの欧文を囲む〔〕）の位置を列挙します。
+pub fn accent_spans(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut rest = 0;
+    while let Some(open) = text[rest..].find('〔').map(|i| rest + i) {
+        let inner = open + '〔'.len_utf8();
+        let Some(close) = text[inner..].find('〕').map(|i| inner + i) else {
+            break;
+        };
+        let content = &text[inner..close];
+        if content.is_ascii() && content.chars().any(|c| c.is_ascii_alphabetic()) {
+            spans.push(open..close + '〕'.len_utf8());
+        }
+        rest = inner;
+    }
+    spans
+}
+
+/// トークン列から、本文で使われている記法とその位置を列挙します。
+pub fn used_notations(tokens: &[Tokenized]) -> Vec<(Notation, Span)> {
+    let mut used = Vec::new();
+    for token in tokens {
+        if let AozoraTokenKind::Text(text) = token.kind {
+            used.extend(accent_spans(text).into_iter().map(|s| {
+                (
+                    Notation::Accent,
+                    s.start + token.span.start..s.end + token.span.start,
+                )
+            }));
+        } else if let Some(notation) = Notation::of_token(&token.kind) {
+            used.push((notation, token.span.clone()));
+        }
+    }
+    used
+}
+
+/// 【テキスト中に現れる記号について】で宣言されていない記法を、記法ごとに最初に使われた位置で報告します。
+pub fn lint_undeclared_notations(
+    declared: &[Notation],
+    used: impl IntoIterator<Item = (Notation, Span)>,
+) -> Vec<Diagnostic> {
+    let mut reported: Vec<Notation> = Vec::new();
+    let mut diagnostics = Vec::new();
+    for (notation, span) in used {
+        if declared.contains(&notation) || reported.contains(&notation) {
+            continue;
+        }
+        reported.push(notation);
+        diagnostics.push(
+            Diagnostic::new(
+                codes::UNDECLARED_NOTATION,
+                Severity::Warning,
+                vec![notation.symbol().to_string(), notation.name().to_string()],
+            )
+            .with_span(span),
+        );
+    }
+    diagnostics
+}
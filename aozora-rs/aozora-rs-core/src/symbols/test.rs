@@ -0,0 +1,75 @@
+use winnow::LocatingSlice;
+
+use crate::{
+    Notation, accent_spans, declared_notations, lint_undeclared_notations, parse_meta,
+    parse_symbol_block, tokenize, used_notations,
+};
+
+const BLOCK: &str = "
+【テキスト中に現れる記号について】
+
+《》：ルビ
+（例）吾輩《わがはい》
+
+［＃］：入力者注　主に外字の説明や、傍点の位置の指定
+　　　（数字は、JIS X 0213の面区点番号またはUnicode、底本のページと行数）
+（例）※［＃「言＋戀」の「心」に代えて「足」、第4水準2-89-13］
+";
+
+#[test]
+fn symbol_block_test() {
+    let entries = parse_symbol_block(BLOCK, 0);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].symbol, "《》");
+    assert_eq!(entries[0].explanation, vec!["ルビ"]);
+    assert_eq!(entries[0].examples, vec!["吾輩《わがはい》"]);
+    assert_eq!(
+        &BLOCK[entries[0].span.clone()],
+        "《》：ルビ\n（例）吾輩《わがはい》"
+    );
+    assert_eq!(entries[1].symbol, "［＃］");
+    assert_eq!(
+        entries[1].explanation,
+        vec![
+            "入力者注　主に外字の説明や、傍点の位置の指定",
+            "（数字は、JIS X 0213の面区点番号またはUnicode、底本のページと行数）"
+        ]
+    );
+    assert_eq!(
+        declared_notations(&entries),
+        vec![Notation::Ruby, Notation::Annotation, Notation::Gaiji]
+    );
+}
+
+#[test]
+fn meta_symbols_test() {
+    let text = format!(
+        "題\n著者\n\n-------------------------------------------------------{}-------------------------------------------------------\n本文\n",
+        BLOCK
+    );
+    let mut input = text.as_str();
+    let meta = parse_meta(&mut input).unwrap();
+    assert_eq!(meta.symbols.len(), 2);
+    // 位置はparse_metaに渡したテキストの先頭からになる
+    assert!(text[meta.symbols[0].span.clone()].starts_with("《》：ルビ"));
+}
+
+#[test]
+fn accent_test() {
+    let text = "〔注〕と〔Quid aliud est mulier nisi amicitiae& inimica〕";
+    let spans = accent_spans(text);
+    assert_eq!(spans.len(), 1);
+    assert!(text[spans[0].clone()].starts_with("〔Quid"));
+}
+
+#[test]
+fn undeclared_notation_test() {
+    let body = "吾輩《わがはい》は〔cafe'〕で｜猫《ねこ》である";
+    let tokens = tokenize(&mut LocatingSlice::new(body)).unwrap();
+    let diagnostics = lint_undeclared_notations(&[Notation::Ruby], used_notations(&tokens));
+    let reported: Vec<_> = diagnostics
+        .iter()
+        .map(|d| &body[d.span.clone().unwrap()])
+        .collect();
+    assert_eq!(reported, vec!["〔cafe'〕", "｜"]);
+}
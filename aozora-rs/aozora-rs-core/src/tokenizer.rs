@@ -2,6 +2,7 @@
 
 mod annotation;
 mod definition;
+mod gaiji;
 mod parser;
 #[cfg(test)]
 mod test;
@@ -11,4 +12,5 @@ pub use annotation::{
     sandwiched::{Sandwiched, SandwichedBegins, SandwichedEnds}, single::Single, wholeline::WholeLine,
 };
 pub use definition::{AozoraTokenKind, Tokenized};
-pub use parser::tokenize;
+pub use gaiji::{GETA, Gaiji, resolve_gaiji};
+pub use parser::{tokenize, tokenize_one};
@@ -149,7 +149,7 @@ fn sandwiched_end(input: &mut Input<'_>) -> Result<SandwichedEnds, WinnowError>
             "横組み".value(SandwichedEnds::HorizontalLayout),
             "行右小書き".value(SandwichedEnds::Sup),
         )),
-        "終わり",
+        alt(("終わり", "おわり", "終り")),
     )
         .map(|(v, _)| v)
         .parse_next(input)
@@ -7,6 +7,8 @@ use winnow::{Parser, combinator::alt};
 pub enum AozoraTokenKind<'s> {
     /// 注記（［＃……］）に対応します。
     Annotation(Annotation<'s>),
+    /// 外字注記（※［＃……］）に対応します。
+    Gaiji(Gaiji<'s>),
     /// ルビ（《……》）に対応します。
     Ruby(&'s str),
     /// ルビ区切り（｜）に対応します。
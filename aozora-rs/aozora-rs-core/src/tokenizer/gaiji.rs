@@ -0,0 +1,68 @@
+use std::borrow::Cow;
+
+use gaiji_chuki_parser::{GaijiChuki, parse_tag};
+use winnow::{
+    Parser,
+    combinator::{delimited, preceded},
+    token::take_until,
+};
+
+use crate::*;
+
+/// 解決できなかった外字の代わりに表示する文字（下駄記号）です。
+pub const GETA: &str = "〓";
+
+/// 外字注記（※［＃……］）に対応します。
+///
+/// 元の注記の文字列と、それを解析した結果、[`resolve_gaiji`]で求めた文字を持ちます。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gaiji<'s> {
+    /// 注記の中身（`※［＃`と`］`の間）です。
+    pub chuki: &'s str,
+    /// 注記を解析した結果です。
+    pub parsed: GaijiChuki<'s>,
+    /// 注記が指す文字です。解決する前と、解決できなかった場合は`None`になります。
+    pub resolved: Option<Cow<'static, str>>,
+}
+
+impl<'s> Gaiji<'s> {
+    /// 注記の中身を解析します。注記として解析できなかった場合は`None`を返します。
+    pub fn new(chuki: &'s str) -> Option<Self> {
+        let parsed = parse_tag(&mut &*chuki).ok()?;
+        Some(Self {
+            chuki,
+            parsed,
+            resolved: None,
+        })
+    }
+
+    /// 表示する文字列を返します。解決できなかった外字は[`GETA`]になります。
+    pub fn as_str(&self) -> &str {
+        self.resolved.as_deref().unwrap_or(GETA)
+    }
+
+    /// 表示する文字列に変換します。解決できなかった外字は[`GETA`]になります。
+    pub fn into_text(self) -> Cow<'static, str> {
+        self.resolved.unwrap_or(Cow::Borrowed(GETA))
+    }
+}
+
+/// トークン列に含まれる外字注記が指す文字を`resolver`で求め、各[`Gaiji`]に格納します。
+///
+/// aozora-rs-coreは外字の対応表を持たないため、`aozora_rs_gaiji::resolve_chuki`などを渡してください。
+pub fn resolve_gaiji(
+    tokens: &mut [Tokenized<'_>],
+    resolver: impl Fn(&GaijiChuki<'_>) -> Option<Cow<'static, str>>,
+) {
+    for token in tokens {
+        if let AozoraTokenKind::Gaiji(gaiji) = &mut token.kind {
+            gaiji.resolved = resolver(&gaiji.parsed);
+        }
+    }
+}
+
+pub(crate) fn gaiji<'s>(input: &mut Input<'s>) -> Result<Gaiji<'s>, WinnowError> {
+    preceded('※', delimited("［＃", take_until(1.., "］"), "］"))
+        .verify_map(Gaiji::new)
+        .parse_next(input)
+}
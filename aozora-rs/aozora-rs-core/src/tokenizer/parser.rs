@@ -4,7 +4,7 @@ use winnow::{
     token::{any, take_till, take_until},
 };
 
-use crate::tokenizer::{annotation::command, *};
+use crate::tokenizer::{annotation::command, gaiji::gaiji, *};
 use crate::*;
 
 fn ruby<'s>(input: &mut Input<'s>) -> Result<&'s str, WinnowError> {
@@ -14,6 +14,7 @@ fn ruby<'s>(input: &mut Input<'s>) -> Result<&'s str, WinnowError> {
 
 fn special<'s>(input: &mut Input<'s>) -> Result<AozoraTokenKind<'s>, WinnowError> {
     alt((
+        gaiji.map(AozoraTokenKind::Gaiji),
         '｜'.value(AozoraTokenKind::RubyDelimiter),
         '\n'.value(AozoraTokenKind::Br),
         delimited(
@@ -31,7 +32,7 @@ fn special<'s>(input: &mut Input<'s>) -> Result<AozoraTokenKind<'s>, WinnowError
 
 fn take_until_special<'s>(input: &mut Input<'s>) -> Result<&'s str, WinnowError> {
     fn fast_skip<'s>(input: &mut Input<'s>) -> Result<(), WinnowError> {
-        take_till(1.., |c| matches!(c, '｜' | '\n' | '［' | '《' | '／'))
+        take_till(1.., |c| matches!(c, '｜' | '\n' | '［' | '《' | '／' | '※'))
             .void()
             .parse_next(input)
     }
@@ -45,15 +46,22 @@ fn take_until_special<'s>(input: &mut Input<'s>) -> Result<&'s str, WinnowError>
         .parse_next(input)
 }
 
+/// 入力の先頭からトークンを1つだけ切り出します。
+///
+/// トークンの切り出しは現在位置より後ろの文字列だけで決まるため、
+/// トークンの境界から読み始めれば文書の一部だけを切り出し直すことができます。
+#[allow(clippy::result_unit_err)]
+pub fn tokenize_one<'s>(input: &mut Input<'s>) -> Result<Tokenized<'s>, WinnowError> {
+    alt((special, take_until_special.map(AozoraTokenKind::Text)))
+        .with_span()
+        .map(|(kind, span)| Tokenized { kind, span })
+        .parse_next(input)
+}
+
 #[doc = include_str!("../../docs/tokenize.md")]
+#[allow(clippy::result_unit_err)]
 pub fn tokenize<'s>(input: &mut Input<'s>) -> Result<Vec<Tokenized<'s>>, WinnowError> {
-    let mut result: Vec<Tokenized> = repeat(
-        0..,
-        alt((special, take_until_special.map(AozoraTokenKind::Text)))
-            .with_span()
-            .map(|(kind, span)| Tokenized { kind, span }),
-    )
-    .parse_next(input)?;
+    let mut result: Vec<Tokenized> = repeat(0.., tokenize_one).parse_next(input)?;
 
     result.retain(|token| match &token.kind {
         AozoraTokenKind::Text(t) => !t.is_empty(),
@@ -1,7 +1,9 @@
+use std::borrow::Cow;
+
 use winnow::LocatingSlice;
 
 use crate::{
-    AozoraTokenKind, BackRefKind, Figure, WholeLine, tokenize,
+    AozoraTokenKind, BackRefKind, Figure, GETA, WholeLine, resolve_gaiji, tokenize, tokenize_one,
     tokenizer::annotation::backref::{BackRef, BackRefSpec},
 };
 
@@ -93,3 +95,46 @@ fn image_insertion() {
         ]
     )
 }
+
+#[test]
+fn tokenize_one_test() {
+    let text = "吾輩《わがはい》は猫である\n名前はまだ無い";
+    let mut input = LocatingSlice::new(text);
+    let mut tokens = Vec::new();
+    while let Ok(token) = tokenize_one(&mut input) {
+        tokens.push((token.kind, token.span));
+    }
+    let expected: Vec<_> = tokenize(&mut LocatingSlice::new(text))
+        .unwrap()
+        .into_iter()
+        .map(|t| (t.kind, t.span))
+        .collect();
+    assert_eq!(tokens, expected);
+}
+
+#[test]
+fn gaiji_test() {
+    let mut tokens = tokenize(&mut LocatingSlice::new(
+        "※［＃「木＋世」、第3水準1-85-56］と※［＃「存在しない字」］",
+    ))
+    .unwrap();
+    resolve_gaiji(&mut tokens, |c| c.sjis.map(|_| Cow::Borrowed("枻")));
+    let tokenized: Vec<_> = tokens.into_iter().map(|t| t.kind).collect();
+    let [
+        AozoraTokenKind::Gaiji(menkuten),
+        AozoraTokenKind::Text("と"),
+        AozoraTokenKind::Gaiji(description),
+    ] = tokenized.as_slice()
+    else {
+        panic!("{:?}", tokenized);
+    };
+
+    assert_eq!(menkuten.chuki, "「木＋世」、第3水準1-85-56");
+    assert_eq!(menkuten.parsed.tag, "「木＋世」");
+    assert_eq!(menkuten.parsed.sjis, Some((1, 85, 56)));
+    assert_eq!(description.chuki, "「存在しない字」");
+    assert_eq!(description.parsed.sjis, None);
+    assert_eq!(menkuten.as_str(), "枻");
+    assert_eq!(description.resolved, None);
+    assert_eq!(description.as_str(), GETA);
+}
@@ -3,33 +3,44 @@
 //! - UUIDは著者名とタイトルを'|'で連結したものをSHA-1でハッシュ化したもの。
 //! - xhtmlはitem/xhtmlの中に連番（sec0000.xhtml、sec0001.xhtml...）で配置される。idは拡張子を除いたファイル名と同じ。
 //! - cssはitem/cssの中に連番（style0000.css、style0001.css）で配置される。idは拡張子を除いたファイル名と同じ。
-//! - 画像はitem/imageの中に名前そのままで配置される。idは連番（image0000、image0001）
+//! - 画像はitem/imageの中に、注記に書かれたパスを正規化した名前で配置される。idは連番（image0000、image0001）
+//! - 表紙画像はitem/cover.拡張子に、表紙ページはitem/xhtml/cover.xhtmlに配置される。idはそれぞれcoverとcover-page。
 
 mod nav;
 mod ncx;
 mod opf;
+#[cfg(test)]
+mod test;
 mod xhtml;
 
-use std::{
-    collections::HashMap,
-    io::{Seek, Write},
-};
+use std::io::{Seek, Write};
 
-use aozora_rs_core::{AZResult, AZResultC, AozoraMeta};
+use aozora_rs_core::{
+    AZResult, AZResultC, Annotation, AozoraMeta, AozoraTokenKind, Diagnostic, Severity, Single,
+    Span, codes, same_path, tokenize,
+};
 use aozora_rs_xhtml::{Chapter, XHTMLResult};
-use aozora_rs_zip::{Dependencies, ImgExtension};
+use aozora_rs_zip::{Dependencies, Image, ImgExtension, normalize_path};
 use time::OffsetDateTime;
 use uuid::Uuid;
+use winnow::LocatingSlice;
 
 use zip::{ZipWriter, result::ZipError, write::SimpleFileOptions};
 
+/// XHTMLのページから見た、挿絵を格納するディレクトリです。
+///
+/// 本文は[`aozora_rs_xhtml::XHTMLConverter::with_image_dir`]にこれを渡して変換してください。
+pub const IMAGE_DIR: &str = "../image/";
+
 /// Epubの生成に関する設定を保持する構造体です。
 ///
 /// languageには言語コードを指定してください。is_rtlが真であれば縦書きのepubが生成されます。
+/// coverを指定すると、その画像を表紙とし、表紙ページを先頭に挿入します。
 pub struct EpubSetting<'s> {
     pub language: &'s str,
     pub is_rtl: bool,
     pub styles: Vec<&'s str>,
+    pub cover: Option<&'s Image>,
 }
 
 impl Default for EpubSetting<'_> {
@@ -38,6 +49,7 @@ impl Default for EpubSetting<'_> {
             language: "ja",
             is_rtl: true,
             styles: Vec::new(),
+            cover: None,
         }
     }
 }
@@ -53,11 +65,16 @@ pub struct TocPageHyle<'a> {
     pub chapters: &'a [Chapter],
 }
 
+/// 扉ページの書き込みロジックの型
+pub type TitlePageWriter = Box<dyn Fn(&mut dyn Write, &TitlePageHyle) -> std::io::Result<()>>;
+/// 目次ページの書き込みロジックの型
+pub type TocPageWriter = Box<dyn Fn(&mut dyn Write, &TocPageHyle) -> std::io::Result<()>>;
+
 /// EPUB生成時に注入可能なページ生成ロジック
 #[derive(Default)]
 pub struct PageInjectors {
-    pub title_page: Option<Box<dyn Fn(&mut dyn Write, &TitlePageHyle) -> std::io::Result<()>>>,
-    pub toc_page: Option<Box<dyn Fn(&mut dyn Write, &TocPageHyle) -> std::io::Result<()>>>,
+    pub title_page: Option<TitlePageWriter>,
+    pub toc_page: Option<TocPageWriter>,
 }
 
 /// epubの生成時に必要なデータをすべてまとめた構造体です。
@@ -66,7 +83,7 @@ pub struct PageInjectors {
 pub(crate) struct EpubWriter<'s> {
     meta: &'s AozoraMeta<'s>,
     nresult: &'s XHTMLResult,
-    image: &'s HashMap<String, (ImgExtension, Vec<u8>)>,
+    image: &'s Dependencies,
     setting: &'s EpubSetting<'s>,
     injectors: &'s PageInjectors,
     lud: time::OffsetDateTime,
@@ -79,6 +96,13 @@ impl EpubWriter<'_> {
         Uuid::new_v5(&namespace, seed.as_bytes())
     }
 
+    /// 表紙画像のパスと画像形式を返します。
+    pub(crate) fn cover(&self) -> Option<(String, ImgExtension)> {
+        self.setting
+            .cover
+            .map(|(ext, _)| (format!("cover.{}", ext.extension()), *ext))
+    }
+
     pub(crate) fn has_title_page(&self) -> bool {
         self.injectors.title_page.is_some()
     }
@@ -103,12 +127,26 @@ impl EpubWriter<'_> {
             .map(|(num, _)| format!("style/style{:>04}.css", num))
     }
 
+    /// 本文から参照された画像を重複なく列挙します。
+    ///
+    /// 画像のパスは正規化したうえで`image/`の下に置かれます。見つからなかった画像は`None`になります。
+    pub(crate) fn dependencies(&self) -> Vec<(&str, Option<&Image>)> {
+        let mut deps: Vec<(&str, Option<&Image>)> = Vec::new();
+        for d in &self.nresult.dependency {
+            if deps
+                .iter()
+                .all(|(seen, _)| normalize_path(seen) != normalize_path(d))
+            {
+                deps.push((d, self.image.get(d).map(|(_, img)| img)));
+            }
+        }
+        deps
+    }
+
     pub(crate) fn images(&self) -> impl Iterator<Item = (String, ImgExtension)> {
-        self.nresult
-            .dependency
-            .iter()
-            .filter_map(|i| Some((i, ImgExtension::from_extension(i)?)))
-            .map(|(i, e)| (format!("images/{i}"), e))
+        self.dependencies()
+            .into_iter()
+            .filter_map(|(d, img)| Some((format!("image/{}", normalize_path(d)), img?.0)))
     }
 
     pub(crate) fn apply_css(
@@ -145,6 +183,40 @@ impl std::fmt::Display for EpubWarning {
     }
 }
 
+impl EpubWarning {
+    /// 診断に変換します。
+    ///
+    /// `original`は本文で、見つからなかったファイルを参照している図の注記の範囲を診断の位置にします。
+    pub fn to_diagnostic(&self, original: &str) -> Diagnostic {
+        match self {
+            EpubWarning::DependencieNotFound(d) => {
+                let diagnostic = Diagnostic::new(
+                    codes::DEPENDENCY_NOT_FOUND,
+                    Severity::Warning,
+                    vec![d.clone()],
+                );
+                match figure_span(original, d) {
+                    Some(span) => diagnostic.with_span(span),
+                    None => diagnostic,
+                }
+            }
+        }
+    }
+}
+
+/// `original`の中で`path`を参照している最初の図の注記の範囲を返します。
+fn figure_span(original: &str, path: &str) -> Option<Span> {
+    let tokens = tokenize(&mut LocatingSlice::new(original)).ok()?;
+    tokens.into_iter().find_map(|t| match t.kind {
+        AozoraTokenKind::Annotation(Annotation::Single(Single::Figure(f)))
+            if same_path(f.path, path) =>
+        {
+            Some(t.span)
+        }
+        _ => None,
+    })
+}
+
 impl Default for EpubWarning {
     fn default() -> Self {
         Self::DependencieNotFound("".into())
@@ -211,7 +283,7 @@ pub fn from_aozora_zip(
     let epub_writer = EpubWriter {
         meta,
         nresult: xhtml,
-        image: &dependencies.images,
+        image: dependencies,
         setting,
         injectors,
         lud: OffsetDateTime::now_utc(),
@@ -233,6 +305,13 @@ pub fn from_aozora_zip(
     writer.start_file("item/nav.xhtml", options)?;
     epub_writer.write_nav(&mut writer)?;
 
+    if let (Some((path, _)), Some((_, bytes))) = (epub_writer.cover(), setting.cover) {
+        writer.start_file(format!("item/{}", path), options)?;
+        writer.write_all(bytes)?;
+        writer.start_file("item/xhtml/cover.xhtml", options)?;
+        epub_writer.write_cover_page(&mut writer, &path)?;
+    }
+
     if let Some(ref title_writer) = injectors.title_page {
         writer.start_file("item/xhtml/title.xhtml", options)?;
         let hyle = TitlePageHyle {
@@ -261,12 +340,12 @@ pub fn from_aozora_zip(
     }
 
     let mut azresult = AZResultC::default();
-    for d in &epub_writer.nresult.dependency {
-        if let Some(img) = epub_writer.image.get(d) {
-            writer.start_file(format!("item/image/{}", d), options)?;
-            writer.write(&img.1)?;
+    for (d, img) in epub_writer.dependencies() {
+        if let Some(img) = img {
+            writer.start_file(format!("item/image/{}", normalize_path(d)), options)?;
+            writer.write_all(&img.1)?;
         } else {
-            azresult.acc_err(EpubWarning::DependencieNotFound(d.clone()));
+            azresult.acc_err(EpubWarning::DependencieNotFound(d.to_string()));
         }
     }
 
@@ -1,12 +1,14 @@
 use std::io::Write;
 
+use aozora_rs_xhtml::escape_xml;
+
 use crate::epub::EpubWriter;
 
 impl EpubWriter<'_> {
     fn write_nav_head(&self, writer: &mut impl Write) -> std::io::Result<()> {
         writer.write_all(b"<head>\n")?;
         writer.write_all(b"\t<meta charset=\"UTF-8\" />\n")?;
-        writeln!(writer, "\t<title>{}</title>", self.meta.title)?;
+        writeln!(writer, "\t<title>{}</title>", escape_xml(self.meta.title))?;
 
         self.apply_css(writer, "style/")?;
 
@@ -19,6 +21,12 @@ impl EpubWriter<'_> {
         writer.write_all(b"\t<nav epub:type=\"landmarks\" id=\"landmarks\" hidden=\"\">\n")?;
         writer.write_all(b"\t\t<h2>Guide</h2>\n")?;
         writer.write_all(b"\t\t<ol>\n")?;
+        if self.cover().is_some() {
+            writer.write_all(
+                "\t\t\t<li><a epub:type=\"cover\" href=\"xhtml/cover.xhtml\">表紙</a></li>\n"
+                    .as_bytes(),
+            )?;
+        }
         writer.write_all(
             "\t\t\t<li><a epub:type=\"toc\" href=\"nav.xhtml\">目次</a></li>\n".as_bytes(),
         )?;
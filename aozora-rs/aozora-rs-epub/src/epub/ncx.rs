@@ -1,5 +1,7 @@
 use std::io::Write;
 
+use aozora_rs_xhtml::escape_xml;
+
 use crate::epub::EpubWriter;
 
 impl EpubWriter<'_> {
@@ -55,7 +57,7 @@ impl EpubWriter<'_> {
         writeln!(
             writer,
             "\n<docTitle>\n\t<text>{}</text>\n</docTitle>",
-            self.meta.title
+            escape_xml(self.meta.title)
         )?;
         self.write_ncx_navmaps(writer)?;
 
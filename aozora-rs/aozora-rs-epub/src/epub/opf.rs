@@ -1,5 +1,6 @@
 use std::io::Write;
 
+use aozora_rs_xhtml::escape_xml;
 use time::format_description::well_known::Rfc3339;
 
 use crate::epub::EpubWriter;
@@ -11,13 +12,30 @@ impl EpubWriter<'_> {
         write!(
             writer,
             "\t\t<!-- 作品名 -->\n\t\t<dc:title id=\"title\">{}</dc:title>\n",
-            self.meta.title
+            escape_xml(self.meta.title)
         )?;
-        write!(
-            writer,
-            "\t\t<!-- 著者名 -->\n\t\t<dc:creator id=\"creator01\">{}</dc:creator>\n",
-            self.meta.author
+        writer.write_all(
+            "\t\t<meta refines=\"#title\" property=\"title-type\">main</meta>\n".as_bytes(),
         )?;
+        if let Some(reading) = self.meta.title_reading {
+            writeln!(
+                writer,
+                "\t\t<meta refines=\"#title\" property=\"file-as\">{}</meta>",
+                escape_xml(reading)
+            )?;
+        }
+        if let Some(subtitle) = self.meta.subtitle {
+            write!(
+                writer,
+                "\t\t<!-- 副題 -->\n\t\t<dc:title id=\"subtitle\">{}</dc:title>\n",
+                escape_xml(subtitle)
+            )?;
+            writer.write_all(
+                "\t\t<meta refines=\"#subtitle\" property=\"title-type\">subtitle</meta>\n"
+                    .as_bytes(),
+            )?;
+        }
+        self.write_opf_creators(writer)?;
         write!(
             writer,
             "\t\t<!-- 言語 -->\n\t\t<dc:language id=\"pub-lang\">{}</dc:language>\n",
@@ -33,6 +51,51 @@ impl EpubWriter<'_> {
             "\t\t<!-- 更新日 -->\n\t\t<meta property=\"dcterms:modified\">{}</meta>\n",
             self.lud.format(&Rfc3339).unwrap()
         )?;
+        if let Some(source) = self.meta.source {
+            write!(
+                writer,
+                "\t\t<!-- 底本 -->\n\t\t<dc:source>{}</dc:source>\n",
+                escape_xml(source)
+            )?;
+        }
+        if let Some(publisher) = self.meta.publisher {
+            write!(
+                writer,
+                "\t\t<!-- 出版者 -->\n\t\t<dc:publisher>{}</dc:publisher>\n",
+                escape_xml(publisher)
+            )?;
+        }
+        if let Some(rights) = self.meta.rights {
+            write!(
+                writer,
+                "\t\t<!-- 権利 -->\n\t\t<dc:rights>{}</dc:rights>\n",
+                escape_xml(rights)
+            )?;
+        }
+        if let Some(collection) = &self.meta.collection {
+            write!(
+                writer,
+                "\t\t<!-- 叢書 -->\n\t\t<meta property=\"belongs-to-collection\" id=\"collection\">{}</meta>\n",
+                escape_xml(collection.name)
+            )?;
+            writer.write_all(
+                "\t\t<meta refines=\"#collection\" property=\"collection-type\">series</meta>\n"
+                    .as_bytes(),
+            )?;
+            if let Some(position) = collection.position {
+                writeln!(
+                    writer,
+                    "\t\t<meta refines=\"#collection\" property=\"group-position\">{}</meta>",
+                    position
+                )?;
+            }
+        }
+        if self.cover().is_some() {
+            // EPUB 2のリーダー向けに表紙画像を示す
+            writer.write_all(
+                "\t\t<!-- 表紙 -->\n\t\t<meta name=\"cover\" content=\"cover\" />\n".as_bytes(),
+            )?;
+        }
         writer.write_all("\t\t<!-- etc. -->\n".as_bytes())?;
         writer.write_all("\t\t<meta property=\"ebpaj:guide-version\">1.1.3</meta>\n".as_bytes())?;
         writer.write_all("\t\t<meta property=\"ibooks:version\">1.1.2</meta>\n".as_bytes())?;
@@ -40,6 +103,49 @@ impl EpubWriter<'_> {
         Ok(())
     }
 
+    /// 著者、翻訳者、編者を、MARCの役割コードで区別して書き込みます。
+    fn write_opf_creators(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        let creators = [
+            (Some(self.meta.author), "aut", self.meta.author_reading),
+            (self.meta.translator, "trl", None),
+            (self.meta.editor, "edt", None),
+        ];
+        writer.write_all("\t\t<!-- 著者名 -->\n".as_bytes())?;
+        for (seq, (name, role, reading)) in creators
+            .into_iter()
+            .filter_map(|(name, role, reading)| Some((name?, role, reading)))
+            .enumerate()
+        {
+            let id = format!("creator{:>02}", seq + 1);
+            writeln!(
+                writer,
+                "\t\t<dc:creator id=\"{}\">{}</dc:creator>",
+                id,
+                escape_xml(name)
+            )?;
+            writeln!(
+                writer,
+                "\t\t<meta refines=\"#{}\" property=\"role\" scheme=\"marc:relators\">{}</meta>",
+                id, role
+            )?;
+            if let Some(reading) = reading {
+                writeln!(
+                    writer,
+                    "\t\t<meta refines=\"#{}\" property=\"file-as\">{}</meta>",
+                    id,
+                    escape_xml(reading)
+                )?;
+            }
+            writeln!(
+                writer,
+                "\t\t<meta refines=\"#{}\" property=\"display-seq\">{}</meta>",
+                id,
+                seq + 1
+            )?;
+        }
+        Ok(())
+    }
+
     fn write_opf_manifest(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
         writer.write_all("\t<manifest>\n\t\t<!-- navigation -->\n".as_bytes())?;
 
@@ -78,6 +184,18 @@ impl EpubWriter<'_> {
             )?;
         }
 
+        // 表紙を宣言
+        if let Some((path, ext)) = self.cover() {
+            writer.write_all("\t\t<!-- cover -->\n".as_bytes())?;
+            writeln!(
+                writer,
+                "\t\t<item id=\"cover\" href=\"{}\" media-type=\"image/{}\" properties=\"cover-image\"/>",
+                path,
+                ext.into_media_type()
+            )?;
+            writer.write_all("\t\t<item id=\"cover-page\" href=\"xhtml/cover.xhtml\" media-type=\"application/xhtml+xml\"/>\n".as_bytes())?;
+        }
+
         // 注入ページを宣言
         if self.has_title_page() {
             writer.write_all("\t\t<item id=\"title-page\" href=\"xhtml/title.xhtml\" media-type=\"application/xhtml+xml\"/>\n".as_bytes())?;
@@ -110,6 +228,9 @@ impl EpubWriter<'_> {
             "\t<spine page-progression-direction=\"{}\" toc=\"ncx\">",
             if self.setting.is_rtl { "rtl" } else { "ltr" }
         )?;
+        if self.cover().is_some() {
+            writer.write_all("\t\t<itemref idref=\"cover-page\" linear=\"yes\" />\n".as_bytes())?;
+        }
         writer.write_all("\t\t<itemref idref=\"nav\" linear=\"yes\" />\n".as_bytes())?;
         if self.has_title_page() {
             writer.write_all("\t\t<itemref idref=\"title-page\" linear=\"yes\" />\n".as_bytes())?;
@@ -0,0 +1,197 @@
+use std::io::{Cursor, Read};
+
+use aozora_rs_core::{AozoraMeta, Collection, codes};
+use aozora_rs_xhtml::XHTMLResult;
+use aozora_rs_zip::{Dependencies, ImgExtension};
+use time::OffsetDateTime;
+use zip::ZipArchive;
+
+use crate::{EpubSetting, EpubWarning, PageInjectors, epub::EpubWriter, from_aozora_zip};
+
+fn xhtml_result() -> XHTMLResult {
+    XHTMLResult {
+        xhtmls: vec!["<p>本文</p>".to_string()],
+        dependency: Vec::new(),
+        chapters: Vec::new(),
+        source_map: Vec::new(),
+    }
+}
+
+type Epub = ZipArchive<Cursor<Vec<u8>>>;
+
+fn build_epub(meta: &AozoraMeta, setting: &EpubSetting) -> Epub {
+    let mut acc = Cursor::new(Vec::new());
+    from_aozora_zip(
+        &mut acc,
+        &Dependencies::default(),
+        &xhtml_result(),
+        setting,
+        meta,
+        &PageInjectors::default(),
+    )
+    .unwrap();
+    ZipArchive::new(acc).unwrap()
+}
+
+fn read(epub: &mut Epub, name: &str) -> String {
+    let mut content = String::new();
+    epub.by_name(name)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    content
+}
+
+#[test]
+fn cover_test() {
+    let meta = AozoraMeta {
+        title: "猫<と>鼠",
+        author: "夏目漱石",
+        ..Default::default()
+    };
+    let cover = (ImgExtension::Png, b"png".to_vec());
+    let setting = EpubSetting {
+        cover: Some(&cover),
+        ..Default::default()
+    };
+    let mut epub = build_epub(&meta, &setting);
+
+    let opf = read(&mut epub, "item/standard.opf");
+    assert!(opf.contains(
+        "<item id=\"cover\" href=\"cover.png\" media-type=\"image/png\" properties=\"cover-image\"/>"
+    ));
+    assert!(opf.contains("<meta name=\"cover\" content=\"cover\" />"));
+    let cover_page = opf.find("<itemref idref=\"cover-page\"").unwrap();
+    let nav = opf.find("<itemref idref=\"nav\"").unwrap();
+    let body = opf.find("idref=\"sec0000\"").unwrap();
+    assert!(cover_page < nav && nav < body);
+
+    let page = read(&mut epub, "item/xhtml/cover.xhtml");
+    assert!(page.contains("<title>猫&lt;と&gt;鼠</title>"));
+    assert!(page.contains("<img src=\"../cover.png\" alt=\"猫&lt;と&gt;鼠\" />"));
+    assert_eq!(read(&mut epub, "item/cover.png"), "png");
+}
+
+#[test]
+fn without_cover_test() {
+    let meta = AozoraMeta {
+        title: "猫",
+        author: "夏目漱石",
+        ..Default::default()
+    };
+    let mut epub = build_epub(&meta, &EpubSetting::default());
+
+    let opf = read(&mut epub, "item/standard.opf");
+    assert!(!opf.contains("cover"));
+    assert!(read(&mut epub, "item/xhtml/sec0000.xhtml").contains("<title>猫</title>"));
+}
+
+#[test]
+fn opf_snapshot_test() {
+    let meta = AozoraMeta {
+        title: "猫&鼠",
+        author: "夏目<漱石>",
+        subtitle: Some("上\"巻\""),
+        translator: Some("訳者'A'"),
+        title_reading: Some("ねこ&ねずみ"),
+        author_reading: Some("なつめ そうせき"),
+        source: Some("「猫&鼠」"),
+        publisher: Some("青空<出版>"),
+        rights: Some("© 青空&文庫"),
+        collection: Some(Collection {
+            name: "<叢書>",
+            position: Some(2),
+        }),
+        ..Default::default()
+    };
+    let xhtml = xhtml_result();
+    let setting = EpubSetting::default();
+    let writer = EpubWriter {
+        meta: &meta,
+        nresult: &xhtml,
+        image: &Dependencies::default(),
+        setting: &setting,
+        injectors: &PageInjectors::default(),
+        lud: OffsetDateTime::UNIX_EPOCH,
+    };
+    let mut opf = Vec::new();
+    writer.write_opf(&mut opf).unwrap();
+    let opf = String::from_utf8(opf).unwrap();
+    let expected = concat!(
+        include_str!("../../assets/opf_header"),
+        "\t<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+        \t\t<!-- 作品名 -->\n\
+        \t\t<dc:title id=\"title\">猫&amp;鼠</dc:title>\n\
+        \t\t<meta refines=\"#title\" property=\"title-type\">main</meta>\n\
+        \t\t<meta refines=\"#title\" property=\"file-as\">ねこ&amp;ねずみ</meta>\n\
+        \t\t<!-- 副題 -->\n\
+        \t\t<dc:title id=\"subtitle\">上&quot;巻&quot;</dc:title>\n\
+        \t\t<meta refines=\"#subtitle\" property=\"title-type\">subtitle</meta>\n\
+        \t\t<!-- 著者名 -->\n\
+        \t\t<dc:creator id=\"creator01\">夏目&lt;漱石&gt;</dc:creator>\n\
+        \t\t<meta refines=\"#creator01\" property=\"role\" scheme=\"marc:relators\">aut</meta>\n\
+        \t\t<meta refines=\"#creator01\" property=\"file-as\">なつめ そうせき</meta>\n\
+        \t\t<meta refines=\"#creator01\" property=\"display-seq\">1</meta>\n\
+        \t\t<dc:creator id=\"creator02\">訳者&apos;A&apos;</dc:creator>\n\
+        \t\t<meta refines=\"#creator02\" property=\"role\" scheme=\"marc:relators\">trl</meta>\n\
+        \t\t<meta refines=\"#creator02\" property=\"display-seq\">2</meta>\n\
+        \t\t<!-- 言語 -->\n\
+        \t\t<dc:language id=\"pub-lang\">ja</dc:language>\n\
+        \t\t<!-- ファイルid -->\n\
+        \t\t<dc:identifier id=\"unique-id\">urn:uuid:634574c6-1d20-5fc8-bedf-55fbf8809432</dc:identifier>\n\
+        \t\t<!-- 更新日 -->\n\
+        \t\t<meta property=\"dcterms:modified\">1970-01-01T00:00:00Z</meta>\n\
+        \t\t<!-- 底本 -->\n\
+        \t\t<dc:source>「猫&amp;鼠」</dc:source>\n\
+        \t\t<!-- 出版者 -->\n\
+        \t\t<dc:publisher>青空&lt;出版&gt;</dc:publisher>\n\
+        \t\t<!-- 権利 -->\n\
+        \t\t<dc:rights>© 青空&amp;文庫</dc:rights>\n\
+        \t\t<!-- 叢書 -->\n\
+        \t\t<meta property=\"belongs-to-collection\" id=\"collection\">&lt;叢書&gt;</meta>\n\
+        \t\t<meta refines=\"#collection\" property=\"collection-type\">series</meta>\n\
+        \t\t<meta refines=\"#collection\" property=\"group-position\">2</meta>\n\
+        \t\t<!-- etc. -->\n\
+        \t\t<meta property=\"ebpaj:guide-version\">1.1.3</meta>\n\
+        \t\t<meta property=\"ibooks:version\">1.1.2</meta>\n\
+        \t</metadata>\n\
+        \n\
+        \t<manifest>\n\
+        \t\t<!-- navigation -->\n\
+        \t\t<item\n\
+        \t\t\tmedia-type=\"application/xhtml+xml\"\n\
+        \t\t\tid=\"nav\"\n\
+        \t\t\thref=\"nav.xhtml\"\n\
+        \t\t\tproperties=\"nav\"\n\
+        \t\t/>\n\
+        \t\t<item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\" />\n\
+        \t\t<!-- style -->\n\
+        \t\t<!-- image -->\n\
+        \t\t<!-- xhtml -->\n\
+        \t\t<item id=\"sec0000\" href=\"xhtml/sec0000.xhtml\" media-type=\"application/xhtml+xml\"/>\n\
+        \t</manifest>\n\
+        \n\
+        \t<spine page-progression-direction=\"rtl\" toc=\"ncx\">\n\
+        \t\t<itemref idref=\"nav\" linear=\"yes\" />\n\
+        \t\t<itemref linear=\"yes\" idref=\"sec0000\" />\n\
+        </spine>\n\
+        \n\
+        </package>"
+    );
+    assert_eq!(opf, expected);
+}
+
+#[test]
+fn missing_dependency_diagnostic_test() {
+    let original = "本文\n［＃挿絵（fig/a.png、横320×縦240）入る］\n";
+    let diagnostic = EpubWarning::DependencieNotFound("./fig/a.png".into()).to_diagnostic(original);
+    assert_eq!(diagnostic.code, codes::DEPENDENCY_NOT_FOUND);
+    assert_eq!(diagnostic.message.args, ["./fig/a.png"]);
+    assert_eq!(
+        &original[diagnostic.span.unwrap()],
+        "［＃挿絵（fig/a.png、横320×縦240）入る］"
+    );
+
+    let diagnostic = EpubWarning::DependencieNotFound("b.png".into()).to_diagnostic(original);
+    assert_eq!(diagnostic.span, None);
+}
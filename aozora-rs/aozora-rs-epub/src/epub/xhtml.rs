@@ -1,5 +1,7 @@
 use std::io::Write;
 
+use aozora_rs_xhtml::escape_xml;
+
 use crate::epub::EpubWriter;
 
 impl EpubWriter<'_> {
@@ -12,7 +14,7 @@ impl EpubWriter<'_> {
 
         writer.write_all(b"<head>\n\t<meta charset=\"UTF-8\" />\n")?;
 
-        writeln!(writer, "\t<title>{}</title>", &self.meta.title)?;
+        writeln!(writer, "\t<title>{}</title>", escape_xml(self.meta.title))?;
         self.apply_css(writer, "../style/")?;
 
         writer.write_all(b"</head>\n<body>\n\t<div class=\"main\">\n")?;
@@ -30,11 +32,34 @@ impl EpubWriter<'_> {
     ) -> Result<(), std::io::Error> {
         writer.write_all(include_bytes!("../../assets/xhtml_header"))?;
         writer.write_all(b"<head>\n\t<meta charset=\"UTF-8\" />\n")?;
-        writeln!(writer, "\t<title>{}</title>", &self.meta.title)?;
+        writeln!(writer, "\t<title>{}</title>", escape_xml(self.meta.title))?;
         self.apply_css(writer, "../style/")?;
         writer.write_all(b"</head>\n<body>\n\t<div class=\"main\">\n")?;
         injector(writer, hyle)?;
         writer.write_all(b"\n\t</div>\n</body>\n</html>\n")?;
         Ok(())
     }
+
+    /// 表紙画像だけを置いた表紙ページを書き込みます。`path`はitemからの表紙画像のパスです。
+    pub(crate) fn write_cover_page(
+        &self,
+        writer: &mut impl Write,
+        path: &str,
+    ) -> Result<(), std::io::Error> {
+        writer.write_all(include_bytes!("../../assets/xhtml_header"))?;
+        writer.write_all(b"<head>\n\t<meta charset=\"UTF-8\" />\n")?;
+        writeln!(writer, "\t<title>{}</title>", escape_xml(self.meta.title))?;
+        writer.write_all(
+            b"\t<style>\n\t\thtml, body { margin: 0; padding: 0; height: 100%; }\n\t\timg { display: block; max-width: 100%; max-height: 100%; margin: auto; }\n\t</style>\n",
+        )?;
+        writer.write_all(b"</head>\n<body epub:type=\"cover\">\n")?;
+        writeln!(
+            writer,
+            "\t<div class=\"cover\"><img src=\"../{}\" alt=\"{}\" /></div>",
+            escape_xml(path),
+            escape_xml(self.meta.title)
+        )?;
+        writer.write_all(b"</body>\n</html>\n")?;
+        Ok(())
+    }
 }
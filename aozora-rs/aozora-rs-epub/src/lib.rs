@@ -1,6 +1,6 @@
 mod epub;
 
 pub use epub::{
-    AozoraEpubError, EpubSetting, EpubWarning, PageInjectors, TitlePageHyle, TocPageHyle,
-    from_aozora_zip,
+    AozoraEpubError, EpubSetting, EpubWarning, IMAGE_DIR, PageInjectors, TitlePageHyle,
+    TitlePageWriter, TocPageHyle, TocPageWriter, from_aozora_zip,
 };
@@ -10,7 +10,7 @@ use winnow::{
 
 pub type Menkuten = (u8, u8, u8);
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GaijiChuki<'s> {
     pub tag: &'s str,
     pub sjis: Option<Menkuten>,
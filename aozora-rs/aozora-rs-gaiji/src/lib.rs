@@ -1,3 +1,5 @@
+#[cfg(all(feature = "gaiji_rev", feature = "menkuten"))]
+mod search;
 #[cfg(test)]
 mod test;
 mod whole;
@@ -6,7 +8,9 @@ use std::borrow::Cow;
 use std::{collections::HashMap, sync::LazyLock};
 use winnow::Parser;
 
-pub use crate::whole::utf8tify_all_gaiji;
+#[cfg(all(feature = "gaiji_rev", feature = "menkuten"))]
+pub use crate::search::{GaijiEntry, gaiji_entries, search_gaiji};
+pub use crate::whole::{OffsetMap, utf8tify_all_gaiji, utf8tify_all_gaiji_with_map};
 pub use gaiji_chuki_parser::{GaijiChuki, parse_tag};
 
 use rkyv::util::AlignedVec;
@@ -60,8 +64,11 @@ pub fn menkuten_to_unicode(plane: u8, row: u8, cell: u8) -> Option<&'static str>
 
 #[cfg(feature = "gaiji")]
 pub fn gaiji_to_char(input: &mut &str) -> Option<Cow<'static, str>> {
-    parse_tag
-        .verify_map(|t| t.to_cow(&GAIJI_TO_CHAR, &MENKUTEN_TO_UNICODE))
-        .parse_next(input)
-        .ok()
+    parse_tag.verify_map(resolve_chuki).parse_next(input).ok()
+}
+
+/// 解析済みの外字注記が指す文字を解決します。
+#[cfg(feature = "gaiji")]
+pub fn resolve_chuki(chuki: GaijiChuki<'_>) -> Option<Cow<'static, str>> {
+    chuki.to_cow(&GAIJI_TO_CHAR, &MENKUTEN_TO_UNICODE)
 }
@@ -0,0 +1,79 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use gaiji_chuki_parser::shift_jis;
+use winnow::Parser;
+
+use crate::{CHAR_TO_GAIJI, MENKUTEN_TO_UNICODE, MenkutenKey};
+
+/// 逆引き検索で得られる外字1件分の情報
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaijiEntry {
+    /// 「木＋世」のような字形の説明
+    pub description: String,
+    /// 対応するUnicode文字列
+    pub character: String,
+    /// JIS X 0213の面区点番号
+    pub menkuten: Option<MenkutenKey>,
+}
+
+impl GaijiEntry {
+    /// `※［＃`と`］`の間に置く注記の中身を`parse_tag`が受理する形式で返す
+    ///
+    /// 面区点番号が分かる場合は水準と面区点番号を、分からない場合はUnicodeの符号位置を添える
+    pub fn to_annotation(&self) -> String {
+        match self.menkuten {
+            Some((men, ku, ten)) => format!(
+                "{}、第{}水準{}-{}-{}",
+                self.description,
+                men + 2,
+                men,
+                ku,
+                ten
+            ),
+            None => {
+                let codes = self
+                    .character
+                    .chars()
+                    .map(|c| format!("+{:04X}", c as u32))
+                    .collect::<String>();
+                format!("{}、U{}", self.description, codes)
+            }
+        }
+    }
+}
+
+static GAIJI_ENTRIES: LazyLock<Vec<GaijiEntry>> = LazyLock::new(|| {
+    let unicode_to_menkuten = MENKUTEN_TO_UNICODE
+        .iter()
+        .map(|(code, unicode)| (unicode.as_str(), *code))
+        .collect::<HashMap<_, _>>();
+    let mut entries = CHAR_TO_GAIJI
+        .iter()
+        .map(|(character, description)| GaijiEntry {
+            description: description.clone(),
+            character: character.clone(),
+            menkuten: unicode_to_menkuten.get(character.as_str()).copied(),
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.description.cmp(&b.description));
+    entries
+});
+
+/// 逆引き可能な外字の一覧を説明の順に返す
+pub fn gaiji_entries() -> &'static [GaijiEntry] {
+    &GAIJI_ENTRIES
+}
+
+/// 字形の説明の一部、面区点番号（`1-85-56`や`第3水準1-85-56`）、
+/// またはUnicode文字そのものから外字を検索する
+pub fn search_gaiji(query: &str) -> Vec<&'static GaijiEntry> {
+    let query = query.trim();
+    let menkuten = shift_jis.parse(query).ok();
+    gaiji_entries()
+        .iter()
+        .filter(|entry| match menkuten {
+            Some(code) => entry.menkuten == Some(code),
+            None => entry.character == query || entry.description.contains(query),
+        })
+        .collect()
+}
@@ -64,3 +64,41 @@ fn convertion_test() {
             .unwrap()
     )
 }
+
+#[cfg(all(feature = "gaiji_rev", feature = "menkuten"))]
+#[test]
+fn search_gaiji_test() {
+    use crate::{gaiji_to_char, search_gaiji};
+
+    let found = search_gaiji("第4水準2-15-55");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].character, "𣜿");
+    assert_eq!(search_gaiji("2-15-55"), found);
+    assert!(search_gaiji("𣜿").contains(&found[0]));
+
+    // 生成した注記はそのまま元の文字に解決できる
+    let annotation = found[0].to_annotation();
+    assert_eq!(gaiji_to_char(&mut annotation.as_str()).unwrap(), "𣜿");
+}
+
+#[test]
+fn offset_map_test() {
+    use crate::utf8tify_all_gaiji_with_map;
+
+    let input = "吾輩※［＃「木＋世」、第3水準1-85-56］は猫";
+    let (converted, err, map) = utf8tify_all_gaiji_with_map(input);
+    assert_eq!(converted, "吾輩枻は猫");
+    assert!(err.is_empty());
+
+    // 外字より前と後ろ
+    assert_eq!(map.to_original(3), 3);
+    let after = input.find("は").unwrap();
+    assert_eq!(map.to_original(converted.find("は").unwrap()), after);
+    // 外字を含む範囲は注記全体に広がる
+    assert_eq!(map.span_to_original(&(6..9)), 6..after);
+    assert_eq!(&input[map.span_to_original(&(0..converted.len()))], input);
+
+    let (converted, _, map) = utf8tify_all_gaiji_with_map("吾輩は猫である");
+    assert!(matches!(converted, std::borrow::Cow::Borrowed(_)));
+    assert_eq!(map.span_to_original(&(3..6)), 3..6);
+}
@@ -1,7 +1,8 @@
 use std::borrow::Cow;
+use std::ops::Range;
 
 use winnow::Parser;
-use winnow::combinator::{alt, delimited, opt, repeat};
+use winnow::combinator::{alt, delimited, opt};
 use winnow::token::{rest, take_until};
 
 type WinnowError = ();
@@ -51,37 +52,80 @@ fn parse_odoriji<'a>(input: &mut &'a str) -> Result<GaijiOrStr<'a>, WinnowError>
         .parse_next(input)
 }
 
+/// 外字変換後のテキスト上の位置から、変換前のテキスト上の位置を求めるための対応表です。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffsetMap {
+    /// 置き換えられた箇所の、変換後の範囲と変換前の範囲の組を位置の昇順に持ちます。
+    replaced: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl OffsetMap {
+    /// 変換後の位置を変換前の位置に変換します。
+    ///
+    /// 置き換えられた文字の途中を指す位置は、置き換え前の注記の先頭に対応させます。
+    pub fn to_original(&self, offset: usize) -> usize {
+        let i = self.replaced.partition_point(|(c, _)| c.start <= offset);
+        match i.checked_sub(1).map(|i| &self.replaced[i]) {
+            None => offset,
+            Some((converted, original)) if offset < converted.end => original.start,
+            Some((converted, original)) => original.end + (offset - converted.end),
+        }
+    }
+
+    /// 変換後の範囲を変換前の範囲に変換します。
+    ///
+    /// 置き換えられた文字を含む範囲は、置き換え前の注記全体を含むように広げます。
+    pub fn span_to_original(&self, span: &Range<usize>) -> Range<usize> {
+        let start = self.to_original(span.start);
+        let i = self.replaced.partition_point(|(c, _)| c.start < span.end);
+        let end = match i.checked_sub(1).map(|i| &self.replaced[i]) {
+            Some((converted, original)) if span.end <= converted.end => original.end,
+            _ => self.to_original(span.end),
+        };
+        start..end.max(start)
+    }
+}
+
+/// テキスト中の外字注記とくの字点を変換し、解決できなかった外字注記の一覧とともに返します。
 pub fn utf8tify_all_gaiji<'s>(input: &'s str) -> (Cow<'s, str>, Vec<&'s str>) {
+    let (converted, err, _) = utf8tify_all_gaiji_with_map(input);
+    (converted, err)
+}
+
+/// [`utf8tify_all_gaiji`]と同様に変換し、変換後の位置から変換前の位置を求める対応表も返します。
+pub fn utf8tify_all_gaiji_with_map<'s>(input: &'s str) -> (Cow<'s, str>, Vec<&'s str>, OffsetMap) {
+    let original = input;
     let mut input = input;
-    let result: Vec<GaijiOrStr> = repeat(0.., alt((parse_gaiji, parse_text, parse_odoriji)))
-        .parse_next(&mut input)
-        .unwrap();
-    if result.is_empty() {
-        (Cow::Borrowed(input), vec![])
-    } else if result.len() == 1 {
-        match result.into_iter().next().unwrap().to_cow() {
-            Ok(o) => (o, vec![]),
-            Err(e) => ("〓".into(), vec![e]),
+    let mut result: Vec<(GaijiOrStr, Range<usize>)> = Vec::new();
+    let mut offset = 0;
+    while let Ok(r) = alt((parse_gaiji, parse_text, parse_odoriji)).parse_next(&mut input) {
+        let consumed = original.len() - input.len();
+        result.push((r, offset..consumed));
+        offset = consumed;
+    }
+
+    let mut map = OffsetMap::default();
+    let mut err = vec![];
+    let mut converted = String::with_capacity(original.len());
+    for (r, span) in result {
+        let piece = match r.to_cow() {
+            Ok(o) => o,
+            Err(e) => {
+                err.push(e);
+                "〓".into()
+            }
+        };
+        if !matches!(r, GaijiOrStr::Str(_)) {
+            map.replaced
+                .push((converted.len()..converted.len() + piece.len(), span));
         }
+        converted.push_str(piece.as_ref());
+    }
+
+    if map.replaced.is_empty() {
+        // 何も置き換えられていなければ、変換後のテキストは入力と一致する
+        (Cow::Borrowed(&original[..offset]), err, map)
     } else {
-        let mut err = vec![];
-        (
-            Cow::Owned(
-                result
-                    .into_iter()
-                    .fold(String::new(), |mut acc: String, r| {
-                        let converted = match r.to_cow() {
-                            Ok(o) => o,
-                            Err(e) => {
-                                err.push(e);
-                                "〓".into()
-                            }
-                        };
-                        acc.push_str(converted.as_ref());
-                        acc
-                    }),
-            ),
-            err,
-        )
+        (Cow::Owned(converted), err, map)
     }
 }
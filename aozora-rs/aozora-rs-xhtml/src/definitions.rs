@@ -1,5 +1,26 @@
+use std::{borrow::Cow, ops::Range};
+
 use itertools::Itertools;
 
+/// XMLの特殊文字（`&`、`<`、`>`、`"`、`'`）を実体参照に置き換えます。
+pub fn escape_xml(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
 pub fn get_xhtml_filename(id: usize) -> String {
     format!("xhtml{:>04}.xhtml", id)
 }
@@ -44,3 +65,13 @@ impl Chapter {
         format!("{}#{}", get_xhtml_filename(self.xhtml_id), self.get_id())
     }
 }
+
+/// 変換元の文字列上の範囲と、XHTML中の要素との対応です。
+pub struct SourceMapping {
+    /// 変換元の文字列上のバイト範囲です。
+    pub span: Range<usize>,
+    /// 要素が含まれるXHTMLの連番です。
+    pub xhtml_id: usize,
+    /// 要素のid属性です。
+    pub element_id: String,
+}
@@ -1,7 +1,7 @@
 mod definitions;
 mod xhtmlnize;
 
-use aozora_rs_core::Page;
+use aozora_rs_core::{Colophon, Page};
 pub use definitions::*;
 pub use xhtmlnize::*;
 
@@ -9,6 +9,8 @@ pub struct XHTMLResult {
     pub xhtmls: Vec<String>,
     pub dependency: Vec<String>,
     pub chapters: Vec<Chapter>,
+    /// [`XHTMLConverter::with_source_map`]で変換した場合のみ、変換元との対応表が格納されます。
+    pub source_map: Vec<SourceMapping>,
 }
 
 /// Vec<Page>からXHTMLResultを生成します。
@@ -19,3 +21,17 @@ pub fn retokenized_to_xhtml(pages: Vec<Page<'_>>) -> XHTMLResult {
     }
     converter.convert()
 }
+
+/// 奥付を`<div class="colophon">`で囲んだXHTMLに変換します。
+///
+/// 奥付の原文を1行ずつ段落にします。行頭の字下げはそのまま残ります。
+pub fn colophon_to_xhtml(colophon: &Colophon<'_>) -> String {
+    let mut buff = String::from("<div class=\"colophon\">\n");
+    for line in colophon.raw.lines().filter(|l| !l.trim().is_empty()) {
+        buff.push_str("\t<p>");
+        buff.push_str(&escape_xml(line));
+        buff.push_str("</p>\n");
+    }
+    buff.push_str("</div>\n");
+    buff
+}
@@ -5,10 +5,10 @@ mod validate;
 
 use std::{borrow::Cow, vec};
 
-use aozora_rs_core::{Deco, Page, Retokenized};
+use aozora_rs_core::{Deco, Gaiji, Page, Retokenized, normalize_path};
 
 use crate::{
-    CDepth, Chapter, XHTMLResult,
+    CDepth, Chapter, SourceMapping, XHTMLResult, escape_xml,
     xhtmlnize::{
         definitions::{XHTMLKind, XHTMLTag},
         validate::validate_xhtml,
@@ -59,6 +59,9 @@ pub struct XHTMLConverter<'s> {
     xhtmls: Vec<Vec<XHTMLTag<'s>>>,
     dependencies: Vec<String>,
     chapters: Vec<Chapter>,
+    source: Option<&'s str>,
+    source_map: Vec<SourceMapping>,
+    image_dir: Option<&'s str>,
 }
 
 impl<'s> Default for XHTMLConverter<'s> {
@@ -75,9 +78,76 @@ impl<'s> XHTMLConverter<'s> {
             xhtmls: Vec::new(),
             dependencies: Vec::new(),
             chapters: Vec::new(),
+            source: None,
+            source_map: Vec::new(),
+            image_dir: None,
         }
     }
 
+    /// 変換元の文字列との対応表を生成するXHTMLConverterを構築します。
+    ///
+    /// 本文のテキストは`id`属性を持つ`<span>`で囲まれ、`source`上のバイト範囲との対応が
+    /// [`XHTMLResult::source_map`]に記録されます。エディタでのプレビューの同期スクロールなどに利用できます。
+    pub fn with_source_map(source: &'s str) -> Self {
+        Self {
+            source: Some(source),
+            ..Self::new()
+        }
+    }
+
+    /// 挿絵を、正規化したパスの先頭に`dir`を付けて参照させます。
+    ///
+    /// EPUBのように、画像をXHTMLとは別の場所に格納する場合に用います。
+    /// 指定しない場合は、図の注記に書かれたパスをそのまま参照します。
+    pub fn with_image_dir(mut self, dir: &'s str) -> Self {
+        self.image_dir = Some(dir);
+        self
+    }
+
+    /// テキストが`source`の部分文字列であれば、その範囲を返します。
+    fn source_span(&self, text: &str) -> Option<std::ops::Range<usize>> {
+        let source = self.source?;
+        // 本文のテキストは変換元の文字列を切り出したものなので、ポインタの差がそのまま位置になる
+        let start = (text.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+        let end = start + text.len();
+        (end <= source.len()).then_some(start..end)
+    }
+
+    fn push_text(&mut self, text: &'s str) {
+        let span = self.source_span(text);
+        self.push_mapped(span, Cow::Borrowed(text));
+    }
+
+    fn push_gaiji(&mut self, gaiji: Gaiji<'s>) {
+        // 注記の中身の範囲を、前後の`※［＃`と`］`を含む注記全体の範囲に広げる
+        let span = self
+            .source_span(gaiji.chuki)
+            .map(|s| s.start - "※［＃".len()..s.end + "］".len());
+        self.push_mapped(span, gaiji.into_text());
+    }
+
+    /// テキストを追加し、変換元での範囲が分かる場合は対応表に記録します。
+    fn push_mapped(&mut self, span: Option<std::ops::Range<usize>>, text: Cow<'s, str>) {
+        let Some(span) = span else {
+            self.buff.push(XHTMLTag::from_kind(XHTMLKind::Text(text)));
+            return;
+        };
+        let element_id = format!("src-{}", self.source_map.len());
+        self.buff.extend([
+            XHTMLTag {
+                kind: XHTMLKind::SpanBegin,
+                attributes: vec![Cow::Owned(format!("id=\"{}\"", element_id))],
+            },
+            XHTMLTag::from_kind(XHTMLKind::Text(text)),
+            XHTMLTag::from_kind(XHTMLKind::SpanEnd),
+        ]);
+        self.source_map.push(SourceMapping {
+            span,
+            xhtml_id: self.xhtmls.len(),
+            element_id,
+        });
+    }
+
     fn flush(&mut self) {
         self.xhtmls.push(std::mem::take(&mut self.buff));
     }
@@ -98,6 +168,7 @@ impl<'s> XHTMLConverter<'s> {
             match s {
                 Retokenized::DecoEnd(d) if d == &end_variant => break,
                 Retokenized::Text(t) => buff.push_str(t),
+                Retokenized::Gaiji(g) => buff.push_str(g.as_str()),
                 _ => (),
             }
         }
@@ -114,9 +185,8 @@ impl<'s> XHTMLConverter<'s> {
     pub fn feed(&mut self, peekable: &mut MultiPeek<IntoIter<Retokenized<'s>>>) {
         while let Some(token) = peekable.next() {
             match token {
-                Retokenized::Text(t) => {
-                    self.buff.push(XHTMLTag::from_kind(XHTMLKind::Text(t)));
-                }
+                Retokenized::Text(t) => self.push_text(t),
+                Retokenized::Gaiji(g) => self.push_gaiji(g),
                 Retokenized::Br => self.buff.push(XHTMLTag::from_kind(XHTMLKind::Br)),
                 Retokenized::Kunten(k) => {
                     self.buff.extend([
@@ -124,7 +194,7 @@ impl<'s> XHTMLConverter<'s> {
                             kind: XHTMLKind::SupBegin,
                             attributes: vec![Cow::Borrowed("class=\"kunten\"")],
                         },
-                        XHTMLTag::from_kind(XHTMLKind::Text(k)),
+                        XHTMLTag::from_kind(XHTMLKind::Text(Cow::Borrowed(k))),
                         XHTMLTag::from_kind(XHTMLKind::SupEnd),
                     ]);
                 }
@@ -134,7 +204,7 @@ impl<'s> XHTMLConverter<'s> {
                             kind: XHTMLKind::SupBegin,
                             attributes: vec![Cow::Borrowed("class=\"okurigana\"")],
                         },
-                        XHTMLTag::from_kind(XHTMLKind::Text(o)),
+                        XHTMLTag::from_kind(XHTMLKind::Text(Cow::Borrowed(o))),
                         XHTMLTag::from_kind(XHTMLKind::SupEnd),
                     ]);
                 }
@@ -146,7 +216,14 @@ impl<'s> XHTMLConverter<'s> {
                     self.buff.push(XHTMLTag {
                         kind: XHTMLKind::Img,
                         attributes: vec![
-                            Cow::Owned(format!("src=\"{}\"", f.path)),
+                            Cow::Owned(match self.image_dir {
+                                Some(dir) => format!(
+                                    "src=\"{}{}\"",
+                                    escape_xml(dir),
+                                    escape_xml(&normalize_path(f.path))
+                                ),
+                                None => format!("src=\"{}\"", escape_xml(f.path)),
+                            }),
                             Cow::Owned(size),
                         ],
                     });
@@ -183,6 +260,7 @@ impl<'s> XHTMLConverter<'s> {
             xhtmls: self.xhtmls.into_iter().map(render_xhtml_tags).collect(),
             dependency: self.dependencies,
             chapters: self.chapters,
+            source_map: self.source_map,
         }
     }
 }
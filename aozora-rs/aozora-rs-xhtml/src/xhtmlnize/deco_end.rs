@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use aozora_rs_core::Deco;
 
 use crate::xhtmlnize::{
@@ -14,7 +16,7 @@ impl<'s> XHTMLConverter<'s> {
             Deco::Ruby(r) => {
                 self.buff.extend([
                     XHTMLTag::from_kind(XHTMLKind::RtBegin),
-                    XHTMLTag::from_kind(XHTMLKind::Text(r)),
+                    XHTMLTag::from_kind(XHTMLKind::Text(Cow::Borrowed(r))),
                     XHTMLTag::from_kind(XHTMLKind::RtEnd),
                     XHTMLTag::from_kind(XHTMLKind::RubyEnd),
                 ]);
@@ -22,7 +24,7 @@ impl<'s> XHTMLConverter<'s> {
             Deco::Mama => {
                 self.buff.extend([
                     XHTMLTag::from_kind(XHTMLKind::RtBegin),
-                    XHTMLTag::from_kind(XHTMLKind::Text("ママ")),
+                    XHTMLTag::from_kind(XHTMLKind::Text(Cow::Borrowed("ママ"))),
                     XHTMLTag::from_kind(XHTMLKind::RtEnd),
                     XHTMLTag::from_kind(XHTMLKind::RubyEnd),
                 ]);
@@ -2,7 +2,7 @@ use std::borrow::Cow;
 use std::fmt::Write;
 
 pub enum XHTMLKind<'s> {
-    Text(&'s str),
+    Text(Cow<'s, str>),
     SpanBegin,
     SpanEnd,
     H1Begin,
@@ -59,11 +59,7 @@ impl XHTMLKind<'_> {
 
     /// 単体で存在することができるか
     pub fn is_single(&self) -> bool {
-        match self {
-            Self::Br => true,
-            Self::Img => true,
-            _ => false,
-        }
+        matches!(self, Self::Br | Self::Img)
     }
 
     pub fn is_block_begin(&self) -> bool {
@@ -106,7 +102,7 @@ impl<'s> XHTMLTag<'s> {
         let mut buff = String::from("<");
         buff.push_str(match self.kind {
             XHTMLKind::Text(t) => {
-                return Cow::Borrowed(t);
+                return t;
             }
             XHTMLKind::Br => "br",
             XHTMLKind::DivBegin => "div",
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod test;
+
+use encoding_rs::{EUC_JP, ISO_2022_JP, SHIFT_JIS, UTF_8};
+
+/// 入力されたテキストの文字コードです。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// 内容から文字コードを判定します。
+    #[default]
+    Auto,
+    /// Shift_JISです。Windowsの拡張文字（CP932）も含みます。
+    ShiftJIS,
+    /// UTF-8です。BOMの有無は問いません。
+    Utf8,
+    /// EUC-JPです。
+    EucJp,
+    /// ISO-2022-JP（JISコード）です。
+    Iso2022Jp,
+}
+
+/// テキストを文字列に変換する際に発生した警告です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeWarning {
+    /// 文字コードを自動で判定しました。
+    Detected(Encoding),
+    /// 変換できないバイト列があり、`count`個の置換文字（U+FFFD）に置き換えました。
+    Replaced { encoding: Encoding, count: usize },
+}
+
+impl DecodeWarning {
+    /// 対処を必要としない、情報としての通知であるかを返します。
+    pub fn is_info(&self) -> bool {
+        matches!(self, Self::Detected(_))
+    }
+}
+
+impl std::fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Detected(e) => write!(f, "文字コードを{}と判定しました", e.name()),
+            Self::Replaced { encoding, count } => write!(
+                f,
+                "{}として解釈できないバイト列を{}箇所、置換文字（U+FFFD）に置き換えました。指定した文字コードが正しいことを確認してください",
+                encoding.name(),
+                count
+            ),
+        }
+    }
+}
+
+/// 文字コードを変換したテキストです。
+pub struct Decoded {
+    /// 改行をLFに統一したテキストです。
+    pub text: String,
+    /// 実際に使用した文字コードです。
+    pub encoding: Encoding,
+    /// 変換の際に発生した警告です。
+    pub warnings: Vec<DecodeWarning>,
+}
+
+impl Encoding {
+    /// 表示用の名前を返します。
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "自動判定",
+            Self::ShiftJIS => "Shift_JIS",
+            Self::Utf8 => "UTF-8",
+            Self::EucJp => "EUC-JP",
+            Self::Iso2022Jp => "ISO-2022-JP",
+        }
+    }
+
+    fn as_encoding_rs(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            Self::Auto | Self::Utf8 => UTF_8,
+            Self::ShiftJIS => SHIFT_JIS,
+            Self::EucJp => EUC_JP,
+            Self::Iso2022Jp => ISO_2022_JP,
+        }
+    }
+
+    /// バイト列の内容から文字コードを判定します。
+    ///
+    /// BOM付きUTF-8、ISO-2022-JPのエスケープシーケンス、UTF-8として妥当かを順に調べ、
+    /// いずれでもなければShift_JISとEUC-JPのうち、より自然な日本語として読める方を選びます。
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"\xEF\xBB\xBF") {
+            return Self::Utf8;
+        }
+        if bytes.is_ascii() {
+            let has_escape = bytes
+                .windows(3)
+                .any(|w| matches!(w, b"\x1B$B" | b"\x1B$@" | b"\x1B(J"));
+            return if has_escape {
+                Self::Iso2022Jp
+            } else {
+                Self::Utf8
+            };
+        }
+        if std::str::from_utf8(bytes).is_ok() {
+            return Self::Utf8;
+        }
+        [Self::ShiftJIS, Self::EucJp]
+            .into_iter()
+            .max_by_key(|e| {
+                let (text, _, _) = e.as_encoding_rs().decode(bytes);
+                japanese_score(&text)
+            })
+            .unwrap_or(Self::ShiftJIS)
+    }
+
+    /// バイト列を文字列に変換し、改行をLFに統一します。
+    ///
+    /// 変換できないバイト列は置換文字に置き換え、その数を警告として報告します。
+    pub fn decode(&self, bytes: &[u8]) -> Decoded {
+        let mut warnings = Vec::new();
+        let encoding = match self {
+            Self::Auto => {
+                let detected = Self::detect(bytes);
+                warnings.push(DecodeWarning::Detected(detected));
+                detected
+            }
+            e => *e,
+        };
+        // UTF-8のBOMはencoding_rsが取り除く
+        let (text, _, had_errors) = encoding.as_encoding_rs().decode(bytes);
+        if had_errors {
+            warnings.push(DecodeWarning::Replaced {
+                encoding,
+                count: text.matches('\u{FFFD}').count(),
+            });
+        }
+        let text = if text.contains('\r') {
+            text.replace("\r\n", "\n")
+        } else {
+            text.into_owned()
+        };
+        Decoded {
+            text,
+            encoding,
+            warnings,
+        }
+    }
+}
+
+/// 日本語の文章として自然であるほど大きな値を返します。
+///
+/// かな、漢字、全角記号を加点し、置換文字や半角カナ、制御文字を減点します。
+fn japanese_score(text: &str) -> i64 {
+    text.chars()
+        .map(|c| match c {
+            '\u{3000}'..='\u{30FF}' | '\u{4E00}'..='\u{9FFF}' | '\u{FF01}'..='\u{FF5E}' => 2,
+            '\u{FFFD}' => -10,
+            '\u{FF61}'..='\u{FF9F}' => -1,
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => -5,
+            _ => 0,
+        })
+        .sum()
+}
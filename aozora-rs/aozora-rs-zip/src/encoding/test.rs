@@ -0,0 +1,56 @@
+use crate::{DecodeWarning, Encoding};
+
+const TEXT: &str = "吾輩は猫である。\r\n名前はまだ無い。\r\n";
+
+fn encode(encoding: &'static encoding_rs::Encoding) -> Vec<u8> {
+    encoding.encode(TEXT).0.into_owned()
+}
+
+#[test]
+fn detect_test() {
+    let mut bom = b"\xEF\xBB\xBF".to_vec();
+    bom.extend(TEXT.as_bytes());
+
+    assert_eq!(Encoding::detect(TEXT.as_bytes()), Encoding::Utf8);
+    assert_eq!(Encoding::detect(&bom), Encoding::Utf8);
+    assert_eq!(
+        Encoding::detect(&encode(encoding_rs::SHIFT_JIS)),
+        Encoding::ShiftJIS
+    );
+    assert_eq!(
+        Encoding::detect(&encode(encoding_rs::EUC_JP)),
+        Encoding::EucJp
+    );
+    assert_eq!(
+        Encoding::detect(&encode(encoding_rs::ISO_2022_JP)),
+        Encoding::Iso2022Jp
+    );
+}
+
+#[test]
+fn decode_test() {
+    let decoded = Encoding::Auto.decode(&encode(encoding_rs::EUC_JP));
+
+    assert_eq!(decoded.text, "吾輩は猫である。\n名前はまだ無い。\n");
+    assert_eq!(decoded.encoding, Encoding::EucJp);
+    assert_eq!(
+        decoded.warnings,
+        vec![DecodeWarning::Detected(Encoding::EucJp)]
+    );
+    assert!(decoded.warnings[0].is_info());
+}
+
+#[test]
+fn replacement_test() {
+    let decoded = Encoding::Utf8.decode(&encode(encoding_rs::SHIFT_JIS));
+
+    assert!(decoded.text.contains('\u{FFFD}'));
+    assert!(matches!(
+        decoded.warnings.as_slice(),
+        [DecodeWarning::Replaced {
+            encoding: Encoding::Utf8,
+            count: 1..
+        }]
+    ));
+    assert!(!decoded.warnings[0].is_info());
+}
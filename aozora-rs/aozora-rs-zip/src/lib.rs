@@ -1,12 +1,25 @@
+mod encoding;
+mod path;
+mod source;
+mod text;
+#[cfg(feature = "writer")]
+mod writer;
+
 use std::{
     collections::HashMap,
     io::{Read, Seek},
-    string::FromUtf8Error,
 };
 
 use winnow::error::ContextError;
 use zip::result::ZipError;
 
+pub use crate::encoding::*;
+pub use crate::path::*;
+pub use crate::source::SourceKind;
+pub use crate::text::ZipText;
+#[cfg(feature = "writer")]
+pub use crate::writer::{to_gaiji_notation, write_aozora_zip};
+
 #[derive(Debug, Clone, Copy)]
 pub enum ImgExtension {
     Png,
@@ -25,36 +38,51 @@ impl ImgExtension {
         }
     }
 
+    /// 画像形式に対応する代表的な拡張子を返します。
+    pub fn extension(&self) -> &str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Gif => "gif",
+            Self::Svg => "svg",
+        }
+    }
+
+    /// 拡張子から画像形式を判定します。大文字と小文字は区別しません。
     pub fn from_extension(from: &str) -> Option<Self> {
-        match from {
-            "jpg" | "jpeg" | "JPG" | "JPEG" => Some(Self::Jpeg),
-            "png" | "PNG" => Some(Self::Png),
-            "gif" | "GIF" => Some(Self::Gif),
-            "svg" | "SVG" => Some(Self::Svg),
+        match from.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "png" => Some(Self::Png),
+            "gif" => Some(Self::Gif),
+            "svg" => Some(Self::Svg),
             _ => None,
         }
     }
+
+    /// パスの拡張子から画像形式を判定します。
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        Self::from_extension(extension)
+    }
 }
 
 /// AozoraZipからepubやXHTMLを生成するときに発生しうるエラーを列挙したエラー型です。
 #[derive(Debug)]
 pub enum AozoraZipError {
     Io(std::io::Error),
-    MultiTextFound,
     NoTextFound,
     BrokenZip(ZipError),
     EncodingError,
-    BrokenText,
     TokenizeFailed(ContextError),
     BrokenMetaData,
     ImgReadFailed(std::io::Error),
+    WriteFailed(ZipError),
 }
 
 impl std::fmt::Display for AozoraZipError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let err: String = match self {
             Self::BrokenMetaData => "メタデータが破損しています".into(),
-            Self::BrokenText => "入力されたテキストデータは破損しています".into(),
             Self::BrokenZip(z) => match z {
                 ZipError::FileNotFound => "必要なファイルが見つかりませんでした".into(),
                 ZipError::InvalidArchive(_) => "無効なアーカイブです".into(),
@@ -66,85 +94,157 @@ impl std::fmt::Display for AozoraZipError {
             Self::EncodingError => "テキストデータの解釈に失敗しました。指定した文字コードが正しいことを確認してください".into(),
             Self::ImgReadFailed(i) => format!("画像の読み込みに失敗しました：{}", i),
             Self::Io(i) => format!("I/Oに失敗しました：{}", i),
-            Self::MultiTextFound => "Zipの中に複数のテキストファイルが見つかりました".into(),
             Self::NoTextFound => "Zipの中にテキストファイルが見つかりませんでした".into(),
+            Self::WriteFailed(z) => format!("Zipの書き込みに失敗しました：{}", z),
             Self::TokenizeFailed(_) => "テキストのトークン化に失敗しました".into()
         };
         write!(f, "{}", err)
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum Encoding {
-    ShiftJIS,
-    Utf8,
+/// Zipファイルから読み込んだ、青空文庫書式の解析に必要なデータを保持する構造体です。
+pub struct AozoraZip {
+    /// 本文と判断したテキストの、[`AozoraZip::texts`]での添字です。
+    pub main: usize,
+    pub images: Dependencies,
+    /// Zipの中に見つかったすべてのテキストを、ファイル名の順に並べたものです。
+    ///
+    /// 分冊された作品を順に結合したい場合などに利用できます。
+    pub texts: Vec<ZipText>,
 }
 
-impl Encoding {
-    pub fn bytes_to_string(&self, bytes: Vec<u8>) -> Result<String, FromUtf8Error> {
-        match self {
-            Self::ShiftJIS => {
-                let (cow, _, _) = encoding_rs::SHIFT_JIS.decode(&bytes);
-                Ok(cow.to_string())
-            }
-            Self::Utf8 => String::from_utf8(bytes),
+/// 画像形式と、画像のバイト列の組です。
+pub type Image = (ImgExtension, Vec<u8>);
+
+/// 本文から参照される画像をまとめた構造体です。
+#[derive(Default, Clone)]
+pub struct Dependencies {
+    /// Zip内のファイル名をキーとした画像です。
+    pub images: HashMap<String, Image>,
+    /// 本文のテキストが置かれたディレクトリです。図の注記のパスはここからの相対パスとして解釈されます。
+    pub base_dir: String,
+}
+
+impl Dependencies {
+    /// 図の注記に書かれたパスから画像を探し、Zip内のファイル名と共に返します。
+    ///
+    /// パスは正規化したうえで大文字と小文字を区別せずに比較し、
+    /// 本文のディレクトリからの相対パス、Zipの最上位からのパスの順に探します。
+    pub fn get(&self, path: &str) -> Option<(&str, &Image)> {
+        if let Some((name, image)) = self.images.get_key_value(path) {
+            return Some((name, image));
         }
+        [join_path(&self.base_dir, path), normalize_path(path)]
+            .iter()
+            .find_map(|candidate| {
+                self.images
+                    .iter()
+                    .find(|(name, _)| same_path(name, candidate))
+            })
+            .map(|(name, image)| (name.as_str(), image))
     }
 }
 
-/// Zipファイルから読み込んだ、青空文庫書式の解析に必要なデータを保持する構造体です。
-pub struct AozoraZip {
-    pub txt: String,
-    pub images: Dependencies,
+/// アーカイブやディレクトリのエントリを1件ずつ受け取り、[`AozoraZip`]を組み立てます。
+pub(crate) struct Collector<'e> {
+    encoding: &'e Encoding,
+    images: HashMap<String, Image>,
+    texts: Vec<ZipText>,
 }
 
-#[derive(Default, Clone)]
-pub struct Dependencies {
-    pub images: HashMap<String, (ImgExtension, Vec<u8>)>,
+impl<'e> Collector<'e> {
+    pub(crate) fn new(encoding: &'e Encoding) -> Self {
+        Self {
+            encoding,
+            images: HashMap::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    /// `name`のエントリがテキストか画像であれば、`reader`から読み込みます。
+    pub(crate) fn add(&mut self, name: &str, mut reader: impl Read) -> Result<(), AozoraZipError> {
+        let extension = name.rsplit_once(".").map(|(_, r)| r).unwrap_or("");
+        if extension.eq_ignore_ascii_case("txt") {
+            self.add_text(name, reader)?;
+        } else if let Some(ext) = ImgExtension::from_extension(extension) {
+            let mut buff: Vec<u8> = Vec::new();
+            reader
+                .read_to_end(&mut buff)
+                .map_err(AozoraZipError::ImgReadFailed)?;
+            self.images.insert(name.into(), (ext, buff));
+        }
+        Ok(())
+    }
+
+    /// `reader`から読み込んだ内容を、文字コードを変換してテキストとして加えます。
+    pub(crate) fn add_text(
+        &mut self,
+        name: &str,
+        mut reader: impl Read,
+    ) -> Result<(), AozoraZipError> {
+        let decoded = {
+            let mut buff: Vec<u8> = Vec::new();
+            reader.read_to_end(&mut buff).map_err(AozoraZipError::Io)?;
+            self.encoding.decode(&buff)
+        };
+        self.texts.push(ZipText {
+            name: name.into(),
+            txt: decoded.text,
+            warnings: decoded.warnings,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<AozoraZip, AozoraZipError> {
+        let Self {
+            images, mut texts, ..
+        } = self;
+        texts.sort_by(|a, b| text::natural_cmp(&a.name, &b.name));
+        // 複数のテキストがあれば、説明書きなどを避けて本文らしいものを選ぶ
+        let main = text::select_main_text(&texts).ok_or(AozoraZipError::NoTextFound)?;
+        Ok(AozoraZip {
+            main,
+            images: Dependencies {
+                images,
+                base_dir: parent_dir(&texts[main].name),
+            },
+            texts,
+        })
+    }
 }
 
 impl AozoraZip {
-    pub fn read_from_zip<'s, T>(zip: T, encoding: &Encoding) -> Result<Self, AozoraZipError>
+    /// 本文と判断したテキストを返します。
+    pub fn main_text(&self) -> &ZipText {
+        &self.texts[self.main]
+    }
+
+    /// 本文と判断したテキストの内容を返します。
+    pub fn txt(&self) -> &str {
+        &self.main_text().txt
+    }
+
+    /// 本文の文字コードを変換した際に発生した警告を返します。
+    pub fn warnings(&self) -> &[DecodeWarning] {
+        &self.main_text().warnings
+    }
+
+    pub fn read_from_zip<T>(zip: T, encoding: &Encoding) -> Result<Self, AozoraZipError>
     where
         T: Read + Seek,
     {
         let mut zip = zip::ZipArchive::new(zip).map_err(AozoraZipError::BrokenZip)?;
-        let mut images = HashMap::new();
-        let mut txt = None;
+        let mut collector = Collector::new(encoding);
 
         let zip_len = zip.len();
         for c in 0..zip_len {
-            let c = zip.by_index(c).map_err(AozoraZipError::BrokenZip);
-            let mut c = c?;
-            let extension = c.name().rsplit_once(".").map(|(_, r)| r).unwrap_or("");
-            if extension == "txt" {
-                let text = {
-                    let mut buff: Vec<u8> = Vec::new();
-                    c.read_to_end(&mut buff).map_err(AozoraZipError::Io)?;
-                    encoding
-                        .bytes_to_string(buff)
-                        .map_err(|_| AozoraZipError::BrokenText)?
-                };
-                if txt.is_none() {
-                    txt = Some(text);
-                } else {
-                    return Err(AozoraZipError::MultiTextFound);
-                }
-            } else if let Some(ext) = ImgExtension::from_extension(extension) {
-                let mut buff: Vec<u8> = Vec::new();
-                c.read_to_end(&mut buff)
-                    .map_err(AozoraZipError::ImgReadFailed)?;
-                images.insert(c.name().into(), (ext, buff));
+            let c = zip.by_index(c).map_err(AozoraZipError::BrokenZip)?;
+            if c.is_dir() {
+                continue;
             }
+            let name = c.name().to_string();
+            collector.add(&name, c)?;
         }
-        let nresult = if let Some(s) = txt {
-            s
-        } else {
-            return Err(AozoraZipError::NoTextFound);
-        };
-        Ok(Self {
-            txt: nresult,
-            images: Dependencies { images },
-        })
+        collector.finish()
     }
 }
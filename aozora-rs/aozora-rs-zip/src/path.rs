@@ -0,0 +1,4 @@
+#[cfg(test)]
+mod test;
+
+pub use aozora_rs_core::{join_path, normalize_path, parent_dir, same_path};
@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{Dependencies, ImgExtension};
+
+#[test]
+fn resolve_test() {
+    let image = (ImgExtension::Png, Vec::new());
+    let deps = Dependencies {
+        images: HashMap::from([
+            ("work/files/fig1.PNG".to_string(), image.clone()),
+            ("fig2.png".to_string(), image),
+        ]),
+        base_dir: "work".to_string(),
+    };
+
+    assert_eq!(
+        deps.get("./files/fig1.png").unwrap().0,
+        "work/files/fig1.PNG"
+    );
+    assert_eq!(deps.get("fig2.png").unwrap().0, "fig2.png");
+    assert!(deps.get("fig3.png").is_none());
+}
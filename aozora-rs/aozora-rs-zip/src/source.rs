@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod test;
+
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use flate2::read::GzDecoder;
+
+use crate::{AozoraZip, AozoraZipError, Collector, Encoding};
+
+/// 作品の読み込み元の種類です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// テキストと画像を納めたディレクトリです。
+    Dir,
+    Zip,
+    Tar,
+    /// gzipで圧縮されたtarです。
+    TarGz,
+    /// 画像を伴わない単体のテキストファイルです。
+    Text,
+}
+
+impl SourceKind {
+    /// パスから読み込み元の種類を判定します。
+    ///
+    /// ディレクトリでなければ拡張子で判定し、既知のアーカイブでなければテキストとみなします。
+    pub fn of(path: &Path) -> Self {
+        if path.is_dir() {
+            return Self::Dir;
+        }
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Self::Zip
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Self::TarGz
+        } else if name.ends_with(".tar") {
+            Self::Tar
+        } else {
+            Self::Text
+        }
+    }
+}
+
+impl AozoraZip {
+    /// パスの種類に応じて、ディレクトリ、zip、tar、tar.gz、テキストファイルのいずれかとして読み込みます。
+    pub fn read_from_path(path: &Path, encoding: &Encoding) -> Result<Self, AozoraZipError> {
+        match SourceKind::of(path) {
+            SourceKind::Dir => Self::read_from_dir(path, encoding),
+            SourceKind::Zip => {
+                let file = File::open(path).map_err(AozoraZipError::Io)?;
+                Self::read_from_zip(BufReader::new(file), encoding)
+            }
+            SourceKind::Tar => {
+                let file = File::open(path).map_err(AozoraZipError::Io)?;
+                Self::read_from_tar(BufReader::new(file), encoding)
+            }
+            SourceKind::TarGz => {
+                let file = File::open(path).map_err(AozoraZipError::Io)?;
+                Self::read_from_tar(GzDecoder::new(BufReader::new(file)), encoding)
+            }
+            SourceKind::Text => {
+                let file = File::open(path).map_err(AozoraZipError::Io)?;
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                let mut collector = Collector::new(encoding);
+                // 拡張子に関わらずテキストとして扱う
+                collector.add_text(name, file)?;
+                collector.finish()
+            }
+        }
+    }
+
+    /// ディレクトリの中にあるテキストと画像を、サブディレクトリも含めて読み込みます。
+    ///
+    /// エントリの名前は`dir`からの相対パスになります。隠しファイルとシンボリックリンクは無視します。
+    pub fn read_from_dir(dir: &Path, encoding: &Encoding) -> Result<Self, AozoraZipError> {
+        let mut collector = Collector::new(encoding);
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let entries = std::fs::read_dir(&current).map_err(AozoraZipError::Io)?;
+            for entry in entries {
+                let entry = entry.map_err(AozoraZipError::Io)?;
+                let path = entry.path();
+                if path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with('.'))
+                {
+                    continue;
+                }
+                // リンクを辿ると循環したり、ディレクトリの外を読み込んだりしうる
+                let file_type = entry.file_type().map_err(AozoraZipError::Io)?;
+                if file_type.is_symlink() {
+                    continue;
+                }
+                if file_type.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let Some(name) = path
+                    .strip_prefix(dir)
+                    .ok()
+                    .and_then(|p| p.to_str())
+                    .map(|p| p.replace('\\', "/"))
+                else {
+                    continue;
+                };
+                let file = File::open(&path).map_err(AozoraZipError::Io)?;
+                collector.add(&name, file)?;
+            }
+        }
+        collector.finish()
+    }
+
+    /// tarアーカイブを読み込みます。tar.gzは[`flate2::read::GzDecoder`]などで展開してから渡してください。
+    pub fn read_from_tar<R: Read>(tar: R, encoding: &Encoding) -> Result<Self, AozoraZipError> {
+        let mut archive = tar::Archive::new(tar);
+        let mut collector = Collector::new(encoding);
+        for entry in archive.entries().map_err(AozoraZipError::Io)? {
+            let entry = entry.map_err(AozoraZipError::Io)?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = entry
+                .path()
+                .map_err(AozoraZipError::Io)?
+                .to_string_lossy()
+                .into_owned();
+            collector.add(&name, entry)?;
+        }
+        collector.finish()
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use flate2::{Compression, write::GzEncoder};
+
+use crate::{AozoraZip, Encoding, SourceKind};
+
+const MAIN: &str =
+    "吾輩は猫である\n夏目漱石\n\n吾輩は猫である。［＃挿絵（fig1.png、横32×縦24）入る］\n";
+
+fn tar_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, *content).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn source_kind_test() {
+    assert_eq!(SourceKind::of(Path::new("work.ZIP")), SourceKind::Zip);
+    assert_eq!(SourceKind::of(Path::new("work.tar")), SourceKind::Tar);
+    assert_eq!(SourceKind::of(Path::new("work.tar.gz")), SourceKind::TarGz);
+    assert_eq!(SourceKind::of(Path::new("work.tgz")), SourceKind::TarGz);
+    assert_eq!(SourceKind::of(Path::new("work.txt")), SourceKind::Text);
+}
+
+#[test]
+fn read_from_tar_test() {
+    let tar = tar_of(&[
+        ("work/main.txt", MAIN.as_bytes()),
+        ("work/fig1.png", b"png"),
+    ]);
+    let azz = AozoraZip::read_from_tar(tar.as_slice(), &Encoding::Utf8).unwrap();
+
+    assert_eq!(azz.txt(), MAIN);
+    assert_eq!(azz.images.base_dir, "work");
+    assert_eq!(azz.images.get("fig1.png").unwrap().0, "work/fig1.png");
+}
+
+#[test]
+fn read_from_path_test() {
+    let dir = std::env::temp_dir().join(format!("aozora-zip-source-{}", std::process::id()));
+    let work = dir.join("work");
+    std::fs::create_dir_all(work.join("files")).unwrap();
+    std::fs::write(work.join("main.txt"), MAIN).unwrap();
+    std::fs::write(work.join("files").join("fig1.png"), b"png").unwrap();
+
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    std::io::Write::write_all(&mut gz, &tar_of(&[("main.txt", MAIN.as_bytes())])).unwrap();
+    std::fs::write(dir.join("work.tar.gz"), gz.finish().unwrap()).unwrap();
+
+    let from_dir = AozoraZip::read_from_path(&work, &Encoding::Utf8).unwrap();
+    let from_tar_gz = AozoraZip::read_from_path(&dir.join("work.tar.gz"), &Encoding::Utf8).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(from_dir.txt(), MAIN);
+    assert!(from_dir.images.get("files/fig1.png").is_some());
+    assert_eq!(from_tar_gz.txt(), MAIN);
+}
+
+#[cfg(unix)]
+#[test]
+fn read_from_dir_skips_symlinks() {
+    let dir = std::env::temp_dir().join(format!("aozora-zip-symlink-{}", std::process::id()));
+    let work = dir.join("work");
+    std::fs::create_dir_all(&work).unwrap();
+    std::fs::write(work.join("main.txt"), MAIN).unwrap();
+    std::fs::write(dir.join("outside.png"), b"png").unwrap();
+    std::os::unix::fs::symlink(&dir, work.join("loop")).unwrap();
+    std::os::unix::fs::symlink(dir.join("outside.png"), work.join("fig1.png")).unwrap();
+
+    let azz = AozoraZip::read_from_dir(&work, &Encoding::Utf8).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(azz.txt(), MAIN);
+    assert!(azz.images.get("fig1.png").is_none());
+    assert!(azz.images.get("loop/outside.png").is_none());
+}
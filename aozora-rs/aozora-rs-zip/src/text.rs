@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod test;
+
+use std::cmp::Ordering;
+
+use aozora_rs_core::parse_meta;
+
+use crate::DecodeWarning;
+
+/// Zipの中に見つかったテキストファイル1件分です。
+#[derive(Debug, Clone)]
+pub struct ZipText {
+    /// Zip内でのファイル名です。
+    pub name: String,
+    /// 文字コードを変換したテキストです。
+    pub txt: String,
+    /// 文字コードを変換した際に発生した警告です。
+    pub warnings: Vec<DecodeWarning>,
+}
+
+/// 説明書きなど、本文ではないと考えられるファイル名に含まれる語です。
+const README_LIKE: &[&str] = &[
+    "readme",
+    "read_me",
+    "license",
+    "copying",
+    "changelog",
+    "はじめに",
+    "お読み",
+    "説明",
+];
+
+impl ZipText {
+    /// ファイル名が説明書きのようであるかを返します。
+    pub fn is_readme_like(&self) -> bool {
+        let stem = self
+            .name
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.name)
+            .to_lowercase();
+        README_LIKE.iter().any(|r| stem.contains(r))
+    }
+
+    /// 先頭にタイトルと著者の記述があり、その後が空行か【テキスト中に現れる記号について】で区切られているかを返します。
+    pub fn has_header(&self) -> bool {
+        let mut rest = self.txt.as_str();
+        parse_meta(&mut rest).is_ok() && rest.lines().next().is_some_and(|l| l.trim().is_empty())
+    }
+
+    /// テキストが青空文庫書式の本文であるらしさを返します。
+    ///
+    /// タイトルと著者の記述を最も重く見て、【テキスト中に現れる記号について】や底本の記述があるほど大きな値になります。
+    pub fn score(&self) -> i32 {
+        let mut score = 0;
+        if self.is_readme_like() {
+            score -= 8;
+        }
+        if self.has_header() {
+            score += 6;
+        }
+        if self.txt.contains("【テキスト中に現れる記号について】") {
+            score += 4;
+        }
+        if self.txt.contains("底本：") {
+            score += 2;
+        }
+        score
+    }
+}
+
+/// `candidates`のうち、本文である可能性が最も高いものの添字を返します。
+///
+/// 同点であれば、より長いテキストを優先します。
+pub(crate) fn select_main_text(candidates: &[ZipText]) -> Option<usize> {
+    candidates
+        .iter()
+        .enumerate()
+        .max_by_key(|(i, c)| (c.score(), c.txt.len(), std::cmp::Reverse(*i)))
+        .map(|(i, _)| i)
+}
+
+/// ファイル名を、数字の並びを数値として比較します。
+///
+/// `part2.txt`が`part10.txt`より前に来るよう、分冊を順に並べるために用います。
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(ca), Some(cb)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let da = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+            let db = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+            let (na, nb) = (
+                a[..da].trim_start_matches('0'),
+                b[..db].trim_start_matches('0'),
+            );
+            let ord = na.len().cmp(&nb.len()).then_with(|| na.cmp(nb));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            (a, b) = (&a[da..], &b[db..]);
+        } else {
+            if ca != cb {
+                return ca.cmp(&cb);
+            }
+            (a, b) = (&a[ca.len_utf8()..], &b[cb.len_utf8()..]);
+        }
+    }
+}
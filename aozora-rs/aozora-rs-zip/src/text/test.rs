@@ -0,0 +1,76 @@
+use std::io::{Cursor, Write};
+
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+use crate::{AozoraZip, Encoding, text::natural_cmp};
+
+const MAIN: &str = "\
+吾輩は猫である
+夏目漱石
+
+-------------------------------------------------------
+【テキスト中に現れる記号について】
+
+《》：ルビ
+-------------------------------------------------------
+
+吾輩《わがはい》は猫である。
+
+底本：「夏目漱石全集1」ちくま文庫、筑摩書房
+";
+
+fn zip_of(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in files {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+#[test]
+fn select_main_text_test() {
+    let bytes = zip_of(&[
+        (
+            "readme.txt",
+            "このファイルについて\n長い説明がここに続きます。\n",
+        ),
+        ("wagahaiwa_nekodearu.txt", MAIN),
+        ("memo.txt", "メモ\n"),
+    ]);
+    let azz = AozoraZip::read_from_zip(Cursor::new(bytes), &Encoding::Utf8).unwrap();
+
+    assert_eq!(azz.txt(), MAIN);
+    assert_eq!(azz.main_text().name, "wagahaiwa_nekodearu.txt");
+    assert_eq!(azz.texts.len(), 3);
+}
+
+#[test]
+fn header_decides_main_text_test() {
+    // 先頭に空行があり、タイトルと著者の記述として読めない
+    let broken = format!("\n{}", MAIN);
+    let bytes = zip_of(&[("a.txt", &broken), ("b.txt", MAIN)]);
+    let azz = AozoraZip::read_from_zip(Cursor::new(bytes), &Encoding::Utf8).unwrap();
+    assert_eq!(azz.main_text().name, "b.txt");
+
+    // 記号の説明や底本に触れているだけの文書より、見出しのある本文を選ぶ
+    let about = "\
+このファイルについて：【テキスト中に現れる記号について】と底本：の見方
+注意事項がここに続きます。
+";
+    let text = "吾輩は猫である\n夏目漱石\n\n吾輩は猫である。\n\n底本：「夏目漱石全集1」ちくま文庫、筑摩書房\n";
+    let bytes = zip_of(&[("about.txt", about), ("neko.txt", text)]);
+    let azz = AozoraZip::read_from_zip(Cursor::new(bytes), &Encoding::Utf8).unwrap();
+    assert_eq!(azz.main_text().name, "neko.txt");
+}
+
+#[test]
+fn natural_cmp_test() {
+    let mut names = vec!["part10.txt", "part2.txt", "part1.txt", "part02b.txt"];
+    names.sort_by(|a, b| natural_cmp(a, b));
+
+    assert_eq!(
+        names,
+        vec!["part1.txt", "part2.txt", "part02b.txt", "part10.txt"]
+    );
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod test;
+
+use std::{
+    collections::HashMap,
+    io::{Seek, Write},
+};
+
+use aozora_rs_gaiji::gaiji_entries;
+use encoding_rs::SHIFT_JIS;
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+use crate::{AozoraZipError, Dependencies, normalize_path};
+
+/// Shift_JISで表現できない文字を外字注記に置き換えます。
+///
+/// 逆引きできる文字は字形の説明と面区点番号（またはUnicodeの符号位置）を持つ注記に、
+/// 逆引きできない文字は説明を`〓`とし、Unicodeの符号位置のみを持つ注記になります。
+/// 置き換えた文字を重複なく返します。
+pub fn to_gaiji_notation(text: &str) -> (String, Vec<char>) {
+    let mut annotations: Option<HashMap<&str, String>> = None;
+    let mut converted = String::with_capacity(text.len());
+    let mut replaced = Vec::new();
+    for c in text.chars() {
+        if is_sjis_encodable(c) {
+            converted.push(c);
+            continue;
+        }
+        // 逆引き表は外字が初めて現れたときにだけ組み立てる
+        let annotations = annotations.get_or_insert_with(|| {
+            gaiji_entries()
+                .iter()
+                .map(|e| (e.character.as_str(), e.to_annotation()))
+                .collect()
+        });
+        let mut buff = [0; 4];
+        match annotations.get(&*c.encode_utf8(&mut buff)) {
+            Some(annotation) => converted.push_str(&format!("※［＃{}］", annotation)),
+            None => converted.push_str(&format!("※［＃「〓」、U+{:04X}］", c as u32)),
+        }
+        if !replaced.contains(&c) {
+            replaced.push(c);
+        }
+    }
+    (converted, replaced)
+}
+
+/// JIS X 0208の範囲のShift_JISで表現できるかを返します。
+///
+/// NEC特殊文字やIBM拡張文字などのCP932による拡張は、青空文庫では外字として扱われるので除外します。
+fn is_sjis_encodable(c: char) -> bool {
+    if c.is_ascii() {
+        return true;
+    }
+    let mut buff = [0; 4];
+    let (bytes, _, had_errors) = SHIFT_JIS.encode(c.encode_utf8(&mut buff));
+    !had_errors && !matches!(bytes.first(), Some(0x87 | 0xED | 0xEE | 0xFA..=0xFC))
+}
+
+/// 青空文庫で配布されている形式のZipを書き込みます。
+///
+/// `text`はShift_JISに変換され、表現できない文字は外字注記に、改行はCRLFに置き換えられて`text_name`に置かれます。
+/// 画像は本文のディレクトリからの相対パスを保ったまま配置されます。
+/// 外字注記に置き換えた文字を返します。
+pub fn write_aozora_zip(
+    acc: impl Write + Seek,
+    text_name: &str,
+    text: &str,
+    dependencies: &Dependencies,
+) -> Result<Vec<char>, AozoraZipError> {
+    let (converted, replaced) = to_gaiji_notation(&text.replace("\r\n", "\n"));
+    let crlf = converted.replace('\n', "\r\n");
+    let (sjis, _, _) = SHIFT_JIS.encode(&crlf);
+
+    let mut writer = ZipWriter::new(acc);
+    let options = SimpleFileOptions::default();
+    writer
+        .start_file(text_name, options)
+        .map_err(AozoraZipError::WriteFailed)?;
+    writer.write_all(&sjis).map_err(AozoraZipError::Io)?;
+
+    let base_dir = normalize_path(&dependencies.base_dir);
+    let mut images = dependencies.images.iter().collect::<Vec<_>>();
+    images.sort_by_key(|(name, _)| name.as_str());
+    for (name, (_, bytes)) in images {
+        let name = normalize_path(name);
+        let relative = match name.strip_prefix(&format!("{}/", base_dir)) {
+            Some(relative) if !base_dir.is_empty() => relative,
+            _ => &name,
+        };
+        writer
+            .start_file(relative, options)
+            .map_err(AozoraZipError::WriteFailed)?;
+        writer.write_all(bytes).map_err(AozoraZipError::Io)?;
+    }
+    writer.finish().map_err(AozoraZipError::WriteFailed)?;
+    Ok(replaced)
+}
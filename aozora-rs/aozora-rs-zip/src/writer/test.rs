@@ -0,0 +1,45 @@
+use std::{collections::HashMap, io::Cursor};
+
+use crate::{AozoraZip, Dependencies, Encoding, ImgExtension, to_gaiji_notation, write_aozora_zip};
+
+#[test]
+fn gaiji_notation_test() {
+    let (converted, replaced) = to_gaiji_notation("枻と😀と猫");
+
+    assert_eq!(
+        converted,
+        "※［＃「木＋世」、第3水準1-85-56］と※［＃「〓」、U+1F600］と猫"
+    );
+    assert_eq!(replaced, vec!['枻', '😀']);
+}
+
+#[test]
+fn round_trip_test() {
+    let text = "吾輩は猫である\n夏目漱石\n\n枻の木。［＃挿絵（files/fig1.png）入る］\n";
+    let dependencies = Dependencies {
+        images: HashMap::from([(
+            "work/files/fig1.png".to_string(),
+            (ImgExtension::Png, b"png".to_vec()),
+        )]),
+        base_dir: "work".to_string(),
+    };
+    let mut acc = Cursor::new(Vec::new());
+    write_aozora_zip(&mut acc, "neko.txt", text, &dependencies).unwrap();
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(acc.get_ref().clone())).unwrap();
+    assert!(archive.by_name("files/fig1.png").is_ok());
+    let raw = {
+        let mut raw = Vec::new();
+        std::io::Read::read_to_end(&mut archive.by_name("neko.txt").unwrap(), &mut raw).unwrap();
+        raw
+    };
+    assert!(raw.windows(2).any(|w| w == b"\r\n"));
+
+    acc.set_position(0);
+    let azz = AozoraZip::read_from_zip(acc, &Encoding::ShiftJIS).unwrap();
+    assert_eq!(
+        azz.txt(),
+        text.replace("枻", "※［＃「木＋世」、第3水準1-85-56］")
+    );
+    assert!(azz.images.get("files/fig1.png").is_some());
+}
@@ -1,5 +1,6 @@
-use aozora_rs_core::{MetaError, RetokenizeError, ScopenizeError, WinnowError};
+use aozora_rs_core::{Diagnostic, MetaError, RetokenizeError, ScopenizeError, WinnowError};
 use aozora_rs_epub::{AozoraEpubError, EpubWarning};
+use aozora_rs_gaiji::OffsetMap;
 use aozora_rs_zip::AozoraZipError;
 
 /// aozora-rsで発生しうるエラーをまとめた列挙型です。
@@ -94,14 +95,49 @@ impl From<EpubWarning> for AozoraWarning {
 }
 
 impl AozoraWarning {
+    /// CLIやLSPなどで共通に扱える診断に変換します。
+    ///
+    /// 関連する注記や修正案を求めるため、原文のメタデータを除いた部分が必要です。
+    pub fn to_diagnostic(&self, original: &str) -> Diagnostic {
+        match self {
+            AozoraWarning::Retokenize(r) => r.to_diagnostic(),
+            AozoraWarning::Scopenize(s) => s.to_diagnostic(original),
+            AozoraWarning::Epub(z) => z.to_diagnostic(original),
+        }
+    }
+
     /// Warningの表示を行います。
     ///
     /// どこでエラーが発生したのかを指し示すため、原文のメタデータを除いた部分が必要です。
     pub fn display(&self, original: &str) -> String {
-        match self {
-            AozoraWarning::Retokenize(r) => r.to_string(),
-            AozoraWarning::Scopenize(s) => s.display(original),
-            AozoraWarning::Epub(z) => z.to_string(),
-        }
+        self.to_diagnostic(original).display(original)
+    }
+
+    /// 外字を変換する前の原文上の位置を指す診断に変換します。
+    ///
+    /// `converted`は外字変換後のテキスト全体、`body_offset`はその中でメタデータを除いた部分が始まる位置、
+    /// `map`は[`utf8tify_all_gaiji_with_map`](crate::utf8tify_all_gaiji_with_map)が返した対応表です。
+    pub fn to_original_diagnostic(
+        &self,
+        converted: &str,
+        body_offset: usize,
+        map: &OffsetMap,
+    ) -> Diagnostic {
+        self.to_diagnostic(&converted[body_offset..])
+            .map_spans(|s| map.span_to_original(&(s.start + body_offset..s.end + body_offset)))
+    }
+
+    /// 外字を変換する前の原文上の位置でWarningの表示を行います。
+    ///
+    /// 引数の意味は[`AozoraWarning::to_original_diagnostic`]と同じです。
+    pub fn display_original(
+        &self,
+        converted: &str,
+        body_offset: usize,
+        original: &str,
+        map: &OffsetMap,
+    ) -> String {
+        self.to_original_diagnostic(converted, body_offset, map)
+            .display(original)
     }
 }
@@ -14,16 +14,24 @@ pub mod internal {
     pub use aozora_rs_core::*;
     pub use aozora_rs_epub::{EpubSetting, from_aozora_zip};
     pub use aozora_rs_gaiji::*;
-    pub use aozora_rs_xhtml::retokenized_to_xhtml;
+    pub use aozora_rs_xhtml::{XHTMLConverter, colophon_to_xhtml, retokenized_to_xhtml};
 }
 
 use internal::*;
 
-pub use aozora_rs_epub::{PageInjectors, TitlePageHyle, TocPageHyle};
-pub use aozora_rs_gaiji::{gaiji_to_char, utf8tify_all_gaiji};
+pub use aozora_rs_core::{
+    AozoraMeta, Collection, Colophon, Diagnostic, Edit, Fix, Label, Notation, Severity, SourceBook,
+    SymbolEntry, codes,
+};
+pub use aozora_rs_epub::{
+    PageInjectors, TitlePageHyle, TitlePageWriter, TocPageHyle, TocPageWriter,
+};
+pub use aozora_rs_gaiji::{
+    OffsetMap, gaiji_to_char, utf8tify_all_gaiji, utf8tify_all_gaiji_with_map,
+};
 pub use aozora_rs_xhtml::{Chapter, XHTMLResult};
-pub use aozora_rs_zip::AozoraZip;
-pub use aozora_rs_zip::{Dependencies, Encoding};
+pub use aozora_rs_zip::{AozoraZip, ZipText};
+pub use aozora_rs_zip::{DecodeWarning, Decoded, Dependencies, Encoding, Image};
 pub use style::{Style, WritingDirection};
 
 pub use errors::*;
@@ -61,29 +69,44 @@ use winnow::LocatingSlice;
 pub struct AozoraDocument<'s> {
     /// メタデータを格納します。
     pub meta: AozoraMeta<'s>,
-    /// 本文（メタデータを除く部分）を格納します。
+    /// 本文（メタデータと奥付を除く部分）を格納します。
     pub text: &'s str,
+    /// 末尾の奥付（底本の記述）を格納します。奥付が無ければ`None`です。
+    pub colophon: Option<Colophon<'s>>,
     dependencies: Option<&'s Dependencies>,
 }
 
 impl<'s> TryFrom<&'s AozoraZip> for AozoraDocument<'s> {
     type Error = AozoraError;
     fn try_from(value: &'s AozoraZip) -> Result<Self, Self::Error> {
-        let (meta, text) = str_to_meta_and_str(value.txt.as_str())?;
+        let (meta, text, colophon) = str_to_meta_and_str(value.txt())?;
         Ok(Self {
             meta,
             text,
+            colophon,
             dependencies: Some(&value.images),
         })
     }
 }
 
-fn str_to_xhtml(text: &str) -> Result<(XHTMLResult, Vec<AozoraWarning>), AozoraError> {
+/// `image_dir`を指定すると、挿絵をその下の正規化したパスで参照します。
+fn str_to_xhtml<'s>(
+    text: &'s str,
+    image_dir: Option<&'s str>,
+) -> Result<(XHTMLResult, Vec<AozoraWarning>), AozoraError> {
     let mut loc = LocatingSlice::new(text);
-    let tokenized = tokenize(&mut loc).map_err(AozoraError::from)?;
+    let mut tokenized = tokenize(&mut loc).map_err(AozoraError::from)?;
+    resolve_gaiji(&mut tokenized, |c| resolve_chuki(c.clone()));
     let ((scopenized, flattoken), scopenized_err) = scopenize(tokenized).into_tuple();
     let (retokenized, retokenized_err) = retokenize(flattoken, scopenized);
-    let xhtml_result = retokenized_to_xhtml(retokenized);
+    let mut converter = XHTMLConverter::new();
+    if let Some(dir) = image_dir {
+        converter = converter.with_image_dir(dir);
+    }
+    for page in retokenized {
+        converter.feed_page(page);
+    }
+    let xhtml_result = converter.convert();
     let warn = scopenized_err
         .into_iter()
         .map(|err| err.into())
@@ -91,10 +114,19 @@ fn str_to_xhtml(text: &str) -> Result<(XHTMLResult, Vec<AozoraWarning>), AozoraE
     Ok((xhtml_result, warn.collect()))
 }
 
-fn str_to_meta_and_str<'s>(text: &'s str) -> Result<(AozoraMeta<'s>, &'s str), AozoraError> {
+fn str_to_meta_and_str<'s>(
+    text: &'s str,
+) -> Result<(AozoraMeta<'s>, &'s str, Option<Colophon<'s>>), AozoraError> {
     let mut cursor = text;
-    let meta = parse_meta(&mut cursor).map_err(AozoraError::from)?;
-    Ok((meta, cursor))
+    let mut meta = parse_meta(&mut cursor).map_err(AozoraError::from)?;
+    let Some((colophon, body)) = parse_colophon(cursor) else {
+        return Ok((meta, cursor, None));
+    };
+    if let Some(source) = &colophon.source {
+        meta.source = meta.source.or(Some(source.name));
+        meta.publisher = meta.publisher.or(source.publisher);
+    }
+    Ok((meta, body, Some(colophon)))
 }
 
 impl<'s> AozoraDocument<'s> {
@@ -104,17 +136,18 @@ impl<'s> AozoraDocument<'s> {
         text: &'s str,
         dependencies: Option<&'s Dependencies>,
     ) -> Result<Self, AozoraError> {
-        let (meta, text) = str_to_meta_and_str(text)?;
+        let (meta, text, colophon) = str_to_meta_and_str(text)?;
         Ok(Self {
             meta,
             text,
+            colophon,
             dependencies,
         })
     }
 
     /// メタデータと本文を直接注入して[`AozoraDocument`]を構築します。
     ///
-    /// textのヘッダにメタデータが記述されていても考慮されません。すでにパースしたメタデータを注入したり、
+    /// textのヘッダのメタデータや末尾の奥付は考慮されません。すでにパースしたメタデータを注入したり、
     /// モックのメタデータを注入する用途を想定しています。
     pub fn from_str_and_meta(
         meta: AozoraMeta<'s>,
@@ -124,6 +157,7 @@ impl<'s> AozoraDocument<'s> {
         Self {
             meta,
             text,
+            colophon: None,
             dependencies,
         }
     }
@@ -148,8 +182,37 @@ impl<'s> AozoraDocument<'s> {
     }
 
     /// 自身のデータからXHTMLを構築して返します。
+    ///
+    /// 奥付がある場合は、本文の後に奥付のページを加えます。
+    /// 挿絵は、図の注記に書かれたパスのまま参照されます。
+    ///
+    /// # Example
+    /// ```
+    /// use aozora_rs::AozoraDocument;
+    ///
+    /// let doc = AozoraDocument::from_str(
+    ///     "タイトル\n著者\n\n本文\n［＃挿絵（./fig/a.png、横320×縦240）入る］\n\n底本：「タイトル」青空文庫、青空出版\n入力：青空太郎\n",
+    ///     None,
+    /// ).unwrap();
+    /// assert_eq!(doc.meta.publisher, Some("青空出版"));
+    ///
+    /// let (xhtml, _) = doc.xhtml().unwrap();
+    /// assert!(xhtml.xhtmls[0].contains("src=\"./fig/a.png\""));
+    /// assert!(xhtml.xhtmls.last().unwrap().contains("<p>入力：青空太郎</p>"));
+    /// ```
     pub fn xhtml(&self) -> Result<(XHTMLResult, Vec<AozoraWarning>), AozoraError> {
-        str_to_xhtml(self.text)
+        self.render(None)
+    }
+
+    fn render(
+        &self,
+        image_dir: Option<&'s str>,
+    ) -> Result<(XHTMLResult, Vec<AozoraWarning>), AozoraError> {
+        let (mut xhtml, warn) = str_to_xhtml(self.text, image_dir)?;
+        if let Some(colophon) = &self.colophon {
+            xhtml.xhtmls.push(colophon_to_xhtml(colophon));
+        }
+        Ok((xhtml, warn))
     }
 
     /// 自身のデータからEPUBを構築し、writerに書き込みます。
@@ -209,7 +272,7 @@ impl<'s> AozoraDocument<'s> {
             Some(s) => s,
             None => &Dependencies::default(),
         };
-        let (xhtml, mut warn) = self.xhtml()?;
+        let (xhtml, mut warn) = self.render(Some(aozora_rs_epub::IMAGE_DIR))?;
         let ((), zip_warn) = aozora_rs_epub::from_aozora_zip(
             writer,
             dependencies,
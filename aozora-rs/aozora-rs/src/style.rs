@@ -1,4 +1,5 @@
 use aozora_rs_epub::EpubSetting;
+use aozora_rs_zip::Image;
 
 /// 縦書き・横書きを指定するための列挙型です。
 #[derive(Debug, Default, Clone, Copy)]
@@ -20,6 +21,7 @@ pub struct Style<'s> {
     use_prelude: bool,
     css: Vec<&'s str>,
     language: &'s str,
+    cover: Option<&'s Image>,
 }
 
 impl Default for Style<'_> {
@@ -29,6 +31,7 @@ impl Default for Style<'_> {
             use_prelude: true,
             css: Vec::new(),
             language: "ja",
+            cover: None,
         }
     }
 }
@@ -66,6 +69,12 @@ impl<'s> Style<'s> {
         self
     }
 
+    /// EPUBの表紙画像を設定します。表紙ページが本文の前に挿入されます。
+    pub fn cover(&mut self, cover: &'s Image) -> &mut Self {
+        self.cover = Some(cover);
+        self
+    }
+
     /// ここまでに蓄積してきたCSSに加え、[`Style`]の設定に基づき、
     /// 必要なCSSを追加して[`Vec<&str>`]として返却します。
     pub fn css(&self) -> Vec<&'s str> {
@@ -86,6 +95,7 @@ impl<'s> Style<'s> {
             language: self.language,
             is_rtl: matches!(self.direction, WritingDirection::Vertical),
             styles: self.css(),
+            cover: self.cover,
         }
     }
 }
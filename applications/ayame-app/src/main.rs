@@ -1,33 +1,87 @@
 #[cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-use std::{f32, fs::File, io::Cursor, path::Path, sync::Arc};
+use std::{f32, fs::File, path::Path, sync::Arc};
 
-use aozora_rs_zip::ImgExtension;
+use aozora_rs_zip::{AozoraZipError, ImgExtension};
 use ayame::{
-    AozoraDocument, AozoraZip, Dependencies, Encoding, PageInjectors, Style, WritingDirection,
+    AozoraDocument, AozoraZip, DecodeWarning, Dependencies, Encoding, MetaOverrides, PageInjectors,
+    Style, WritingDirection, ZipText,
 };
 use gpui::{
-    App, Application, Bounds, Context, Div, FontWeight, Image, ImageFormat, ImageSource, Window,
-    WindowBounds, WindowOptions, actions, div, img, prelude::*, px, rgb, rgba, size,
+    App, Application, Bounds, Context, Div, Entity, FontWeight, Image, ImageFormat, ImageSource,
+    Window, WindowBounds, WindowOptions, actions, div, img, prelude::*, px, rgb, rgba, size,
 };
 use gpui_component::{
     Icon, StyledExt,
     button::{Button, ButtonCustomVariant, ButtonVariants, DropdownButton},
     checkbox::Checkbox,
+    input::{Input, InputState},
     scroll::ScrollableElement,
     switch::Switch,
 };
-use rfd::FileDialog;
+use rfd::{FileDialog, MessageDialog, MessageLevel};
 
-actions!(ayame, [SelectShiftJIS, SelectUtf8]);
+actions!(
+    ayame,
+    [
+        SelectAuto,
+        SelectShiftJIS,
+        SelectUtf8,
+        SelectEucJp,
+        SelectIso2022Jp
+    ]
+);
 
 struct AyameApp {
     source: Option<(String, Dependencies)>,
+    /// 本文の文字コードを変換した際の警告
+    warnings: Vec<DecodeWarning>,
     cover: Option<(Vec<u8>, ImgExtension)>,
     writing_direction: WritingDirection,
     encoding: Encoding,
     use_prelude: bool,
     use_miyabi: bool,
     consider_gaiji: bool,
+    meta_inputs: MetaInputs,
+}
+
+/// テキストからは読み取れないメタデータの入力欄
+struct MetaInputs {
+    title_reading: Entity<InputState>,
+    author_reading: Entity<InputState>,
+    rights: Entity<InputState>,
+    collection: Entity<InputState>,
+    collection_position: Entity<InputState>,
+}
+
+impl MetaInputs {
+    fn new(window: &mut Window, cx: &mut Context<AyameApp>) -> Self {
+        let mut input = |placeholder: &'static str| {
+            cx.new(|cx| InputState::new(window, cx).placeholder(placeholder))
+        };
+        Self {
+            title_reading: input("タイトルの読み"),
+            author_reading: input("著者の読み（例：みやざわ けんじ）"),
+            rights: input("権利に関する記述"),
+            collection: input("叢書・シリーズ名"),
+            collection_position: input("巻数"),
+        }
+    }
+
+    /// 入力された値を読み出す。空欄の項目は指定しなかったものとして扱う
+    fn overrides(&self, cx: &App) -> MetaOverrides {
+        let value = |input: &Entity<InputState>| {
+            let value = input.read(cx).value();
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        MetaOverrides {
+            title_reading: value(&self.title_reading),
+            author_reading: value(&self.author_reading),
+            rights: value(&self.rights),
+            collection: value(&self.collection),
+            collection_position: value(&self.collection_position).and_then(|p| p.parse().ok()),
+        }
+    }
 }
 
 fn img_ext_to_img_fmt(img_ext: ImgExtension) -> ImageFormat {
@@ -39,29 +93,28 @@ fn img_ext_to_img_fmt(img_ext: ImgExtension) -> ImageFormat {
     }
 }
 
-impl Default for AyameApp {
-    fn default() -> Self {
+impl AyameApp {
+    fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
         Self {
             source: None,
+            warnings: Vec::new(),
             cover: None,
             writing_direction: WritingDirection::Vertical,
-            encoding: Encoding::ShiftJIS,
+            encoding: Encoding::Auto,
             use_prelude: true,
             use_miyabi: true,
             consider_gaiji: true,
+            meta_inputs: MetaInputs::new(window, cx),
         }
     }
 }
 
-fn is_zip(path: &Path) -> bool {
-    path.extension().map(|ext| ext == "zip").unwrap_or(false)
-}
-
 impl AyameApp {
     // Colours
     const OUTLINE_COLOUR: u32 = 0x334155;
     const BUTTON_BACKGROUND: u32 = 0x1e2b4d;
     const LIGHT_TEXT_COLOUR: u32 = 0x90a1b9;
+    const WARNING_TEXT_COLOUR: u32 = 0xfbbf24;
 
     // Sizes
     const VERTICALIZE_THRESHOLD: f32 = 600.;
@@ -77,6 +130,34 @@ impl AyameApp {
         style
     }
 
+    /// ファイル、アーカイブ、ディレクトリのいずれかから作品を読み込む
+    fn load(&mut self, path: &Path) -> Result<(), AozoraZipError> {
+        let AozoraZip {
+            main,
+            images,
+            mut texts,
+        } = AozoraZip::read_from_path(path, &self.encoding)?;
+        let ZipText { txt, warnings, .. } = texts.swap_remove(main);
+        let text = if self.consider_gaiji {
+            let (converted, _) = ayame::utf8tify_all_gaiji(&txt);
+            converted.into_owned()
+        } else {
+            txt
+        };
+        self.source = Some((text, images));
+        self.warnings = warnings;
+        Ok(())
+    }
+
+    /// 読み込みに失敗したことをダイアログで知らせる
+    fn show_load_error(path: &Path, error: AozoraZipError) {
+        MessageDialog::new()
+            .set_level(MessageLevel::Error)
+            .set_title("読み込みに失敗しました")
+            .set_description(format!("{}\n{}", path.display(), error))
+            .show();
+    }
+
     fn get_meta(&self) -> (String, String) {
         self.source
             .as_ref()
@@ -102,25 +183,29 @@ impl AyameApp {
             .on_click(cx.listener(|view, _, _, cx| {
                 if let Some(picked) = FileDialog::new()
                     .set_title("変換したいファイルを選択してください")
-                    .add_filter("変換対象", &["txt", "zip"])
+                    .add_filter("変換対象", &["txt", "zip", "tar", "gz", "tgz"])
                     .pick_file()
                 {
-                    let read = std::fs::read(&picked).unwrap();
-                    let (text, deps) = if is_zip(&picked) {
-                        let azz =
-                            AozoraZip::read_from_zip(Cursor::new(read), &view.encoding).unwrap();
-                        (azz.txt, azz.images)
-                    } else {
-                        let txt = view.encoding.bytes_to_string(read).unwrap();
-                        (txt, Dependencies::default())
-                    };
-                    let text = if view.consider_gaiji {
-                        let (converted, _) = ayame::utf8tify_all_gaiji(&text);
-                        converted.into_owned()
-                    } else {
-                        text
-                    };
-                    view.source = Some((text, deps));
+                    if let Err(e) = view.load(&picked) {
+                        Self::show_load_error(&picked, e);
+                    }
+                    cx.notify();
+                }
+            }));
+        let load_dir_btn = Button::new("dir-select-button")
+            .icon(Icon::default().path("icons/load.svg"))
+            .h(px(save_and_load_height))
+            .flex_1()
+            .label("フォルダを選択")
+            .custom(btn_colour)
+            .on_click(cx.listener(|view, _, _, cx| {
+                if let Some(picked) = FileDialog::new()
+                    .set_title("変換したい作品のフォルダを選択してください")
+                    .pick_folder()
+                {
+                    if let Err(e) = view.load(&picked) {
+                        Self::show_load_error(&picked, e);
+                    }
                     cx.notify();
                 }
             }));
@@ -130,7 +215,7 @@ impl AyameApp {
             .flex_1()
             .label("保存する")
             .custom(btn_colour)
-            .on_click(cx.listener(move |view, _, _, _| {
+            .on_click(cx.listener(move |view, _, _, cx| {
                 if let (Some(save_to), Some((text, deps))) = (
                     FileDialog::new()
                         .add_filter("EPUB", &["epub"])
@@ -138,8 +223,14 @@ impl AyameApp {
                         .save_file(),
                     view.source.clone(),
                 ) {
-                    let doc = AozoraDocument::from_str(text.as_str(), Some(&deps)).unwrap();
-                    let style = view.build_style();
+                    let overrides = view.meta_inputs.overrides(cx);
+                    let mut doc = AozoraDocument::from_str(text.as_str(), Some(&deps)).unwrap();
+                    overrides.apply(&mut doc.meta);
+                    let cover = view.cover.clone().map(|(data, ext)| (ext, data));
+                    let mut style = view.build_style();
+                    if let Some(cover) = cover.as_ref() {
+                        style.cover(cover);
+                    }
                     let injectors = PageInjectors {
                         title_page: Some(ayame::title_page_writer()),
                         toc_page: Some(ayame::toc_page_writer()),
@@ -155,6 +246,7 @@ impl AyameApp {
             .w_full()
             .gap_3()
             .child(load_btn)
+            .child(load_dir_btn)
             .child(save_btn)
     }
 
@@ -183,6 +275,25 @@ impl AyameApp {
             )
     }
 
+    fn render_warnings(&self) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .text_sm()
+            .children(self.warnings.iter().map(|warning| {
+                let (label, colour) = if warning.is_info() {
+                    ("情報", Self::LIGHT_TEXT_COLOUR)
+                } else {
+                    ("警告", Self::WARNING_TEXT_COLOUR)
+                };
+                div()
+                    .text_color(rgb(colour))
+                    .child(format!("{}: {}", label, warning))
+            }))
+    }
+
     fn render_cover(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
         let cover_base = div()
             .bg(rgb(0x121a2b))
@@ -268,6 +379,7 @@ impl AyameApp {
             )
             .child(div().w_full().h(px(1.)).bg(rgba(0x33415560)))
             .child(self.render_save_and_load(cx))
+            .child(self.render_warnings())
     }
 
     fn setting_island() -> Div {
@@ -331,16 +443,39 @@ impl AyameApp {
             .child("文字コード")
             .child(
                 DropdownButton::new("encoding")
-                    .button(Button::new("btn_encoding").label(match self.encoding {
-                        Encoding::ShiftJIS => "Shift-JIS",
-                        Encoding::Utf8 => "UTF-8",
-                    }))
+                    .button(Button::new("btn_encoding").label(self.encoding.name()))
                     .dropdown_menu(|menu, _, _| {
-                        menu.menu("Shift-JIS", Box::new(SelectShiftJIS))
-                            .menu("UTF-8", Box::new(SelectUtf8))
+                        menu.menu(Encoding::Auto.name(), Box::new(SelectAuto))
+                            .menu(Encoding::ShiftJIS.name(), Box::new(SelectShiftJIS))
+                            .menu(Encoding::Utf8.name(), Box::new(SelectUtf8))
+                            .menu(Encoding::EucJp.name(), Box::new(SelectEucJp))
+                            .menu(Encoding::Iso2022Jp.name(), Box::new(SelectIso2022Jp))
                     }),
             );
 
+        let meta_island = Self::setting_island()
+            .text_color(rgb(0xffffff))
+            .child("メタデータ")
+            .child(Input::new(&self.meta_inputs.title_reading))
+            .child(Input::new(&self.meta_inputs.author_reading))
+            .child(Input::new(&self.meta_inputs.rights))
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_2()
+                    .child(
+                        div()
+                            .flex_1()
+                            .child(Input::new(&self.meta_inputs.collection)),
+                    )
+                    .child(
+                        div()
+                            .w(px(80.))
+                            .child(Input::new(&self.meta_inputs.collection_position)),
+                    ),
+            );
+
         div()
             .flex()
             .flex_col()
@@ -351,6 +486,7 @@ impl AyameApp {
             .child(write_direction_island)
             .child(css_island)
             .child(encoding_island)
+            .child(meta_island)
     }
 }
 
@@ -360,6 +496,10 @@ impl Render for AyameApp {
             .size_full()
             .bg(rgb(0x0a101e))
             .overflow_y_scrollbar()
+            .on_action(cx.listener(|view: &mut Self, _: &SelectAuto, _, cx| {
+                view.encoding = Encoding::Auto;
+                cx.notify();
+            }))
             .on_action(cx.listener(|view: &mut Self, _: &SelectShiftJIS, _, cx| {
                 view.encoding = Encoding::ShiftJIS;
                 cx.notify();
@@ -368,6 +508,14 @@ impl Render for AyameApp {
                 view.encoding = Encoding::Utf8;
                 cx.notify();
             }))
+            .on_action(cx.listener(|view: &mut Self, _: &SelectEucJp, _, cx| {
+                view.encoding = Encoding::EucJp;
+                cx.notify();
+            }))
+            .on_action(cx.listener(|view: &mut Self, _: &SelectIso2022Jp, _, cx| {
+                view.encoding = Encoding::Iso2022Jp;
+                cx.notify();
+            }))
             .child(
                 div()
                     .min_h_full()
@@ -396,7 +544,7 @@ fn main() {
                 window_bounds: Some(WindowBounds::Windowed(bounds)),
                 ..Default::default()
             },
-            |_, cx| cx.new(|_| AyameApp::default()),
+            |window, cx| cx.new(|cx| AyameApp::new(window, cx)),
         )
         .unwrap();
     });
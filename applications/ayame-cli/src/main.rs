@@ -1,7 +1,6 @@
-use std::io::Cursor;
-
 use ayame::{
-    AozoraDocument, AozoraZip, Dependencies, Encoding, PageInjectors, Style, WritingDirection,
+    AozoraDocument, AozoraWarning, AozoraZip, Dependencies, Encoding, MetaOverrides, OffsetMap,
+    PageInjectors, Style, WritingDirection, ZipText,
 };
 use clap::{Args, Parser, Subcommand};
 use rayon::prelude::*;
@@ -28,6 +27,79 @@ enum Commands {
     Xhtml(CommonArgs),
     /// 青空文庫書式のファイルからEPUBを生成
     Epub(CommonArgs),
+    /// 青空文庫の配布形式（Shift_JISのテキストと画像を納めたZip）にまとめる
+    Pack(PackArgs),
+}
+
+/// 入力の文字コードを指定するコマンドライン引数
+#[derive(Args)]
+struct EncodingArgs {
+    /// 入力の文字コード（auto、sjis、utf8、euc-jp、iso-2022-jp）
+    #[arg(long, value_parser = parse_encoding, default_value = "auto")]
+    encoding: Encoding,
+
+    /// `--encoding utf8`と同じ
+    #[arg(long)]
+    utf8: bool,
+}
+
+impl EncodingArgs {
+    fn to_encoding(&self) -> Encoding {
+        if self.utf8 {
+            Encoding::Utf8
+        } else {
+            self.encoding
+        }
+    }
+}
+
+/// テキストからは読み取れないメタデータを指定するコマンドライン引数
+#[derive(Args)]
+struct MetaArgs {
+    /// 並べ替えに用いるタイトルの読み
+    #[arg(long)]
+    title_reading: Option<String>,
+
+    /// 並べ替えに用いる著者の読み（例：みやざわ けんじ）
+    #[arg(long)]
+    author_reading: Option<String>,
+
+    /// 権利に関する記述
+    #[arg(long)]
+    rights: Option<String>,
+
+    /// 作品が属する叢書やシリーズの名前
+    #[arg(long)]
+    collection: Option<String>,
+
+    /// 叢書やシリーズの中での巻数
+    #[arg(long, requires = "collection")]
+    collection_position: Option<u32>,
+}
+
+impl MetaArgs {
+    fn to_overrides(&self) -> MetaOverrides {
+        MetaOverrides {
+            title_reading: self.title_reading.clone(),
+            author_reading: self.author_reading.clone(),
+            rights: self.rights.clone(),
+            collection: self.collection.clone(),
+            collection_position: self.collection_position,
+        }
+    }
+}
+
+/// Packのコマンドライン引数
+#[derive(Args)]
+struct PackArgs {
+    #[arg(required = true)]
+    sources: Vec<PathBuf>,
+
+    #[command(flatten)]
+    encoding: EncodingArgs,
+
+    #[arg(short, long)]
+    output: Option<PathBuf>,
 }
 
 /// XhtmlとEpubで共通のコマンドライン引数
@@ -36,8 +108,8 @@ struct CommonArgs {
     #[arg(required = true)]
     sources: Vec<PathBuf>,
 
-    #[arg(long)]
-    utf8: bool,
+    #[command(flatten)]
+    encoding: EncodingArgs,
 
     #[arg(long)]
     horizontal: bool,
@@ -54,6 +126,9 @@ struct CommonArgs {
     #[arg(long)]
     no_gaiji: bool,
 
+    #[command(flatten)]
+    meta: MetaArgs,
+
     #[arg(short, long)]
     output: Option<PathBuf>,
 }
@@ -94,26 +169,22 @@ fn get_file_stem(source: &Path) -> Result<String> {
     source
         .file_stem()
         .and_then(|s| s.to_str())
-        .map(|s| s.to_string())
+        // work.tar.gzのような二重の拡張子を取り除く
+        .map(|s| s.strip_suffix(".tar").unwrap_or(s).to_string())
         .ok_or_else(|| format!("ファイル名を取得できませんでした: {}", source.display()).into())
 }
 
-fn to_encoding(utf8: bool) -> Encoding {
-    if utf8 {
-        Encoding::Utf8
-    } else {
-        Encoding::ShiftJIS
+fn parse_encoding(s: &str) -> std::result::Result<Encoding, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "auto" => Ok(Encoding::Auto),
+        "sjis" | "shift_jis" | "shift-jis" | "cp932" => Ok(Encoding::ShiftJIS),
+        "utf8" | "utf-8" => Ok(Encoding::Utf8),
+        "euc-jp" | "eucjp" => Ok(Encoding::EucJp),
+        "iso-2022-jp" | "jis" => Ok(Encoding::Iso2022Jp),
+        _ => Err(format!("不明な文字コードです: {}", s)),
     }
 }
 
-fn is_zip(source: &Path) -> bool {
-    source
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| e.eq_ignore_ascii_case("zip"))
-        .unwrap_or(false)
-}
-
 fn read_extra_css(extra_css: &[PathBuf]) -> Result<Vec<String>> {
     extra_css
         .iter()
@@ -127,38 +198,81 @@ fn read_extra_css(extra_css: &[PathBuf]) -> Result<Vec<String>> {
         .collect()
 }
 
+/// 読み込んだソースファイル
+struct Source {
+    /// 外字を変換する前のテキスト
+    original: String,
+    /// 外字を変換した後のテキスト
+    text: String,
+    /// `text`上の位置から`original`上の位置を求めるための対応表
+    map: OffsetMap,
+    deps: Dependencies,
+}
+
+impl Source {
+    /// `body`で発生した警告を、外字を変換する前のテキスト上の位置で表示する
+    fn display_warning(&self, warning: &AozoraWarning, body: &str) -> String {
+        let body_offset = body.as_ptr() as usize - self.text.as_ptr() as usize;
+        warning.display_original(&self.text, body_offset, &self.original, &self.map)
+    }
+}
+
 /// ソースファイルを読み込み、テキストと画像依存を返す
-fn read_source(source: &Path, encoding: &Encoding, gaiji: bool) -> Result<(String, Dependencies)> {
-    let bytes = fs::read(source)?;
-    let (text, deps) = if is_zip(source) {
-        let azz =
-            AozoraZip::read_from_zip(Cursor::new(bytes), encoding).map_err(|e| e.to_string())?;
-        (azz.txt, azz.images)
-    } else {
-        let txt = encoding.bytes_to_string(bytes).map_err(|e| e.to_string())?;
-        (txt, Dependencies::default())
-    };
-    let text = if gaiji {
-        let (converted, _) = aozora_rs::utf8tify_all_gaiji(&text);
-        converted.into_owned()
+fn read_source(source: &Path, encoding: &Encoding, gaiji: bool) -> Result<Source> {
+    let azz = AozoraZip::read_from_path(source, encoding).map_err(|e| e.to_string())?;
+    if azz.texts.len() > 1 {
+        eprintln!(
+            "\n警告 ({}): 複数のテキストファイルのうち、{}を本文として扱います",
+            source.display(),
+            azz.main_text().name
+        );
+    }
+    let AozoraZip {
+        main,
+        images: deps,
+        mut texts,
+    } = azz;
+    let ZipText {
+        txt: original,
+        warnings,
+        ..
+    } = texts.swap_remove(main);
+    for warning in &warnings {
+        let level = if warning.is_info() {
+            "情報"
+        } else {
+            "警告"
+        };
+        eprintln!("\n{} ({}): {}", level, source.display(), warning);
+    }
+    let (text, map) = if gaiji {
+        let (converted, _, map) = aozora_rs::utf8tify_all_gaiji_with_map(&original);
+        (converted.into_owned(), map)
     } else {
-        text
+        (original.clone(), OffsetMap::default())
     };
-    Ok((text, deps))
+    Ok(Source {
+        original,
+        text,
+        map,
+        deps,
+    })
 }
 
 fn handle_xhtml(source: &Path, args: &CommonArgs, style: &Style, output_dir: &Path) -> Result<()> {
     let timer = std::time::Instant::now();
     let file_stem = get_file_stem(source)?;
 
-    let (text, deps) = read_source(source, &to_encoding(args.utf8), !args.no_gaiji)?;
-    let txt = &mut text.as_str();
-    let meta = aozora_rs::internal::parse_meta(txt).map_err(|e| e.to_string())?;
-    let doc = AozoraDocument::from_str_and_meta(meta, txt, Some(&deps));
+    let src = read_source(source, &args.encoding.to_encoding(), !args.no_gaiji)?;
+    let doc = AozoraDocument::from_str(&src.text, Some(&src.deps)).map_err(|e| e.to_string())?;
 
     let (xhtml, errors) = ayame::to_browser_xhtml(&doc, style).map_err(|e| e.to_string())?;
     for error in &errors {
-        eprintln!("\n警告 ({}): {}", source.display(), error.display(txt));
+        eprintln!(
+            "\n警告 ({}): {}",
+            source.display(),
+            src.display_warning(error, doc.text)
+        );
     }
 
     let output_path = output_dir.join(format!("{}.xhtml", file_stem));
@@ -175,10 +289,11 @@ fn handle_xhtml(source: &Path, args: &CommonArgs, style: &Style, output_dir: &Pa
 fn handle_epub(source: &Path, args: &CommonArgs, style: &Style, output_dir: &Path) -> Result<()> {
     let timer = std::time::Instant::now();
 
-    let (text, deps) = read_source(source, &to_encoding(args.utf8), !args.no_gaiji)?;
-    let txt = &mut text.as_str();
-    let meta = aozora_rs::internal::parse_meta(txt).map_err(|e| e.to_string())?;
-    let doc = AozoraDocument::from_str_and_meta(meta, txt, Some(&deps));
+    let src = read_source(source, &args.encoding.to_encoding(), !args.no_gaiji)?;
+    let overrides = args.meta.to_overrides();
+    let mut doc =
+        AozoraDocument::from_str(&src.text, Some(&src.deps)).map_err(|e| e.to_string())?;
+    overrides.apply(&mut doc.meta);
 
     let output_path = output_dir.join(format!("[{}] {}.epub", doc.meta.author, doc.meta.title));
     let mut file = fs::File::create(&output_path)?;
@@ -192,7 +307,57 @@ fn handle_epub(source: &Path, args: &CommonArgs, style: &Style, output_dir: &Pat
         .epub(&mut file, style, &injectors)
         .map_err(|e| e.to_string())?;
     for w in &warnings {
-        eprintln!("\n警告 ({}): {}", source.display(), w.display(txt));
+        eprintln!(
+            "\n警告 ({}): {}",
+            source.display(),
+            src.display_warning(w, doc.text)
+        );
+    }
+
+    println!(
+        "生成完了 [{:?}] -> {}",
+        timer.elapsed(),
+        output_path.display()
+    );
+    Ok(())
+}
+
+/// 2つのパスが同じファイルを指すかを返す。どちらかが存在しなければ偽
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn handle_pack(source: &Path, args: &PackArgs, output_dir: &Path) -> Result<()> {
+    let timer = std::time::Instant::now();
+    let file_stem = get_file_stem(source)?;
+
+    // 外字注記はそのまま残し、表現できない文字だけを注記に置き換える
+    let src = read_source(source, &args.encoding.to_encoding(), false)?;
+    let output_path = output_dir.join(format!("{}.zip", file_stem));
+    if is_same_file(source, &output_path) {
+        return Err(format!(
+            "出力先が入力と同じファイルです。-oで別の出力先を指定してください: {}",
+            output_path.display()
+        )
+        .into());
+    }
+    let file = fs::File::create(&output_path)?;
+    let replaced = aozora_rs_zip::write_aozora_zip(
+        file,
+        &format!("{}.txt", file_stem),
+        &src.original,
+        &src.deps,
+    )
+    .map_err(|e| e.to_string())?;
+    if !replaced.is_empty() {
+        eprintln!(
+            "\n警告 ({}): Shift_JISで表現できない文字を外字注記に置き換えました：{}",
+            source.display(),
+            replaced.iter().collect::<String>()
+        );
     }
 
     println!(
@@ -229,6 +394,15 @@ fn main() -> Result<()> {
                 }
             });
         }
+        Commands::Pack(args) => {
+            let output_dir = get_output_dir(&args.output)?;
+
+            args.sources.par_iter().for_each(|source| {
+                if let Err(e) = handle_pack(source, args, &output_dir) {
+                    eprintln!("エラー ({}): {:?}", source.display(), e);
+                }
+            });
+        }
     }
 
     Ok(())
@@ -1,10 +1,9 @@
-use std::io::Write;
-
 pub use aozora_rs::{
-    AozoraDocument, AozoraError, AozoraWarning, AozoraZip, Chapter, PageInjectors, Style,
-    TitlePageHyle, TocPageHyle, WritingDirection, XHTMLResult, utf8tify_all_gaiji,
+    AozoraDocument, AozoraError, AozoraMeta, AozoraWarning, AozoraZip, Chapter, Collection,
+    OffsetMap, PageInjectors, Style, TitlePageHyle, TitlePageWriter, TocPageHyle, TocPageWriter,
+    WritingDirection, XHTMLResult, utf8tify_all_gaiji, utf8tify_all_gaiji_with_map,
 };
-pub use aozora_rs::{Dependencies, Encoding};
+pub use aozora_rs::{DecodeWarning, Dependencies, Encoding, ZipText};
 
 pub const MIYABI_CSS: &str = include_str!("../assets/miyabi.css");
 
@@ -13,6 +12,42 @@ pub fn apply_miyabi(style: &mut Style) {
     style.add_css(MIYABI_CSS);
 }
 
+/// テキストからは読み取れず、利用者が指定するメタデータ
+#[derive(Default, Clone)]
+pub struct MetaOverrides {
+    /// 並べ替えに用いるタイトルの読み
+    pub title_reading: Option<String>,
+    /// 並べ替えに用いる著者の読み
+    pub author_reading: Option<String>,
+    /// 権利に関する記述
+    pub rights: Option<String>,
+    /// 作品が属する叢書やシリーズの名前
+    pub collection: Option<String>,
+    /// 叢書やシリーズの中での巻数
+    pub collection_position: Option<u32>,
+}
+
+impl MetaOverrides {
+    /// 指定された項目だけをメタデータに上書き
+    pub fn apply<'a>(&'a self, meta: &mut AozoraMeta<'a>) {
+        if let Some(reading) = &self.title_reading {
+            meta.title_reading = Some(reading);
+        }
+        if let Some(reading) = &self.author_reading {
+            meta.author_reading = Some(reading);
+        }
+        if let Some(rights) = &self.rights {
+            meta.rights = Some(rights);
+        }
+        if let Some(name) = &self.collection {
+            meta.collection = Some(Collection {
+                name,
+                position: self.collection_position,
+            });
+        }
+    }
+}
+
 /// ブラウザ表示可能な完全XHTMLファイルを生成
 pub fn to_browser_xhtml(
     doc: &AozoraDocument,
@@ -43,7 +78,7 @@ pub fn to_browser_xhtml(
 }
 
 /// 扉ページの書き込みロジックを生成
-pub fn title_page_writer() -> Box<dyn Fn(&mut dyn Write, &TitlePageHyle) -> std::io::Result<()>> {
+pub fn title_page_writer() -> TitlePageWriter {
     Box::new(|writer, hyle| {
         writeln!(writer, "<h1 class=\"title\">{}</h1>", hyle.title)?;
         writeln!(writer, "<p class=\"author\">{}</p>", hyle.author)?;
@@ -52,7 +87,7 @@ pub fn title_page_writer() -> Box<dyn Fn(&mut dyn Write, &TitlePageHyle) -> std:
 }
 
 /// 目次ページの書き込みロジックを生成
-pub fn toc_page_writer() -> Box<dyn Fn(&mut dyn Write, &TocPageHyle) -> std::io::Result<()>> {
+pub fn toc_page_writer() -> TocPageWriter {
     Box::new(|writer, hyle| {
         writer.write_all("<h1 class=\"toc-title\">目　次</h1>\n".as_bytes())?;
         writer.write_all(b"<ol class=\"toc-list\">\n")?;
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use tower_lsp::lsp_types::{
+    self, CodeAction, CodeActionKind, CodeActionOrCommand, TextEdit, Url, WorkspaceEdit,
+};
+
+use crate::diagnostics::{to_lsp_diagnostic, to_range};
+use crate::document::DocumentState;
+
+/// 指定範囲にかかるスコープ化エラーのクイックフィックスを生成する
+pub fn compute_code_actions(
+    doc: &DocumentState,
+    uri: &Url,
+    range: lsp_types::Range,
+) -> Vec<CodeActionOrCommand> {
+    doc.scopenize_errors
+        .iter()
+        .filter_map(|error| {
+            let core = error.to_diagnostic(&doc.text);
+            let diagnostic = to_lsp_diagnostic(doc, uri, &core);
+            if diagnostic.range.end < range.start || range.end < diagnostic.range.start {
+                return None;
+            }
+            let fix = core.fix?;
+            let edits = fix
+                .edits
+                .iter()
+                .map(|edit| TextEdit {
+                    range: to_range(doc, &edit.span),
+                    new_text: edit.replacement.clone(),
+                })
+                .collect();
+            Some(CodeActionOrCommand::CodeAction(CodeAction {
+                title: fix.title.text(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), edits)])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(true),
+                ..Default::default()
+            }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::TEST_HEADER;
+
+    /// 本文に対するクイックフィックスを1件取り出し、適用後の本文を返す
+    fn apply_fix(body: &str) -> Option<(String, String)> {
+        let doc = DocumentState::parse_body(body);
+        let uri = Url::parse("file:///test.txt").unwrap();
+        let whole = to_range(&doc, &(0..doc.text.len()));
+        let actions = compute_code_actions(&doc, &uri, whole);
+        let CodeActionOrCommand::CodeAction(action) = actions.into_iter().next()? else {
+            return None;
+        };
+        let mut edits = action.edit?.changes?.remove(&uri)?;
+        // 後ろの編集から適用してオフセットのずれを避ける
+        edits.sort_by_key(|e| std::cmp::Reverse(e.range.start));
+        let mut text = doc.text.clone();
+        for edit in edits {
+            let span = doc.line_index.range_to_span(&doc.text, edit.range);
+            text.replace_range(span, &edit.new_text);
+        }
+        let body = text.strip_prefix(TEST_HEADER).unwrap().to_string();
+        Some((action.title, body))
+    }
+
+    #[test]
+    fn close_unclosed_note() {
+        let (title, body) = apply_fix("［＃太字］吾輩は猫である\n").unwrap();
+        assert_eq!(title, "［＃太字終わり］を挿入する");
+        assert_eq!(body, "［＃太字］吾輩は猫である［＃太字終わり］\n");
+
+        let (_, body) = apply_fix("［＃２段階小さな文字］吾輩\n").unwrap();
+        assert_eq!(body, "［＃２段階小さな文字］吾輩［＃小さな文字終わり］\n");
+    }
+
+    #[test]
+    fn delete_isolated_end() {
+        let (_, body) = apply_fix("吾輩は猫である［＃太字終わり］\n").unwrap();
+        assert_eq!(body, "吾輩は猫である\n");
+    }
+
+    #[test]
+    fn rewrite_backref_to_sandwiched() {
+        let (_, body) = apply_fix("吾輩は猫である。［＃「猫」は太字］\n").unwrap();
+        assert_eq!(body, "吾輩は［＃太字］猫［＃太字終わり］である。\n");
+
+        let (_, body) = apply_fix("吾輩は猫である。［＃「猫」に傍点］\n").unwrap();
+        assert_eq!(body, "吾輩は［＃傍点］猫［＃傍点終わり］である。\n");
+    }
+
+    #[test]
+    fn insert_ruby_delimiter() {
+        let (_, body) = apply_fix("これはスコップ《しゃべる》です\n").unwrap();
+        assert_eq!(body, "これは｜スコップ《しゃべる》です\n");
+    }
+
+    #[test]
+    fn no_fix_for_valid_text() {
+        assert!(apply_fix("吾輩は猫《ねこ》である。\n").is_none());
+    }
+}
@@ -1,5 +1,7 @@
+use aozora_rs_gaiji::search_gaiji;
 use tower_lsp::lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionTextEdit, InsertTextFormat, TextEdit,
+    CompletionItem, CompletionItemKind, CompletionList, CompletionTextEdit, InsertTextFormat,
+    TextEdit,
 };
 
 use crate::document::DocumentState;
@@ -23,6 +25,42 @@ pub fn compute_completions(
     }
 }
 
+/// 一度に返す外字の補完候補の上限
+const GAIJI_COMPLETION_LIMIT: usize = 100;
+
+/// `※［＃「`の直後に提示する外字注記の補完候補を生成する。
+/// `prefix`は`［＃`の後にすでに入力されている文字列で、`「`以降を字形の説明、
+/// 面区点番号、Unicode文字のいずれかとして検索する。
+pub fn compute_gaiji_completions(
+    prefix: &str,
+    replace_range: tower_lsp::lsp_types::Range,
+) -> CompletionList {
+    let query = prefix.trim_start_matches('「').trim_end_matches('」');
+    let found = search_gaiji(query);
+    let items = found
+        .iter()
+        .take(GAIJI_COMPLETION_LIMIT)
+        .map(|entry| CompletionItem {
+            label: format!("{} {}", entry.character, entry.description),
+            kind: Some(CompletionItemKind::VALUE),
+            detail: Some(entry.to_annotation()),
+            // 絞り込みはサーバー側で済ませているので、入力済みの文字列をそのまま使う
+            filter_text: Some(prefix.to_string()),
+            insert_text_format: Some(InsertTextFormat::PLAIN_TEXT),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range: replace_range,
+                new_text: format!("{}］", entry.to_annotation()),
+            })),
+            ..Default::default()
+        })
+        .collect();
+    CompletionList {
+        // 入力が進むたびに検索し直してもらう
+        is_incomplete: true,
+        items,
+    }
+}
+
 fn item(
     label: &str,
     insert: &str,
@@ -140,3 +178,45 @@ fn all_completion_items(r: tower_lsp::lsp_types::Range) -> Vec<CompletionItem> {
         item("ページの左右中央", "ページの左右中央］", "ページ中央寄せ", kw, false, r),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gaiji_labels(prefix: &str) -> Vec<String> {
+        compute_gaiji_completions(prefix, Default::default())
+            .items
+            .into_iter()
+            .map(|item| item.label)
+            .collect()
+    }
+
+    #[test]
+    fn gaiji_by_description() {
+        let list = compute_gaiji_completions("「木＋世", Default::default());
+        assert_eq!(list.items.len(), 1);
+        assert_eq!(list.items[0].label, "枻 「木＋世」");
+        let Some(CompletionTextEdit::Edit(edit)) = &list.items[0].text_edit else {
+            unreachable!()
+        };
+        assert_eq!(edit.new_text, "「木＋世」、第3水準1-85-56］");
+    }
+
+    #[test]
+    fn gaiji_by_menkuten_and_character() {
+        assert_eq!(gaiji_labels("「1-85-56"), ["枻 「木＋世」"]);
+        assert_eq!(gaiji_labels("「第4水準2-15-55」"), ["𣜿 「木＋雲」"]);
+        assert_eq!(gaiji_labels("「枻"), ["枻 「木＋世」"]);
+    }
+
+    #[test]
+    fn inserted_annotation_resolves() {
+        for item in compute_gaiji_completions("「", Default::default()).items {
+            let Some(CompletionTextEdit::Edit(edit)) = item.text_edit else {
+                unreachable!()
+            };
+            let annotation = edit.new_text.strip_suffix('］').unwrap();
+            assert!(aozora_rs::gaiji_to_char(&mut &*annotation).is_some());
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::ops::Range;
+
+use tower_lsp::lsp_types::{GotoDefinitionResponse, Location, Position, Url};
+
+use crate::diagnostics::to_range;
+use crate::document::{DocumentState, OwnedAnnotation, OwnedToken, OwnedTokenKind};
+
+/// 前方参照型注記が装飾している文字列の範囲を返す
+fn backref_target(doc: &DocumentState, index: usize, target: &str) -> Option<Range<usize>> {
+    // 同じ文字列に続けて付いたルビや前方参照型注記を飛ばし、直前のテキストを探す
+    let text = doc.tokens[..index].iter().rev().find(|t| {
+        !matches!(
+            t.kind,
+            OwnedTokenKind::Ruby(_) | OwnedTokenKind::Annotation(OwnedAnnotation::BackRef { .. })
+        )
+    })?;
+    if !matches!(text.kind, OwnedTokenKind::Text) || !doc.text[text.span.clone()].ends_with(target)
+    {
+        return None;
+    }
+    let span = (text.span.end - target.len())..text.span.end;
+    // 前方参照に失敗した注記はスコープを持たない
+    doc.scopes.iter().any(|s| s.span == span).then_some(span)
+}
+
+fn is_end(token: &OwnedToken, multiline: bool) -> bool {
+    match &token.kind {
+        OwnedTokenKind::Annotation(OwnedAnnotation::SandwichedEnd { .. }) => !multiline,
+        OwnedTokenKind::Annotation(OwnedAnnotation::MultilineEnd { .. }) => multiline,
+        _ => false,
+    }
+}
+
+fn is_begin(token: &OwnedToken, multiline: bool) -> bool {
+    match &token.kind {
+        OwnedTokenKind::Annotation(OwnedAnnotation::SandwichedBegin { .. }) => !multiline,
+        OwnedTokenKind::Annotation(OwnedAnnotation::MultilineBegin { .. }) => multiline,
+        _ => false,
+    }
+}
+
+/// 開始側の注記と対になる終了側の注記の範囲を返す。
+/// スコープは開始側の直後から終了側の直前までなので、両端からたどれる
+fn closing_partner(
+    doc: &DocumentState,
+    begin: &OwnedToken,
+    multiline: bool,
+) -> Option<Range<usize>> {
+    doc.scopes
+        .iter()
+        .filter(|s| s.span.start == begin.span.end)
+        .find_map(|s| {
+            doc.tokens
+                .iter()
+                .find(|t| t.span.start == s.span.end && is_end(t, multiline))
+        })
+        .map(|t| t.span.clone())
+}
+
+/// 終了側の注記と対になる開始側の注記の範囲を返す
+fn opening_partner(doc: &DocumentState, end: &OwnedToken, multiline: bool) -> Option<Range<usize>> {
+    doc.scopes
+        .iter()
+        .filter(|s| s.span.end == end.span.start)
+        .find_map(|s| {
+            doc.tokens
+                .iter()
+                .find(|t| t.span.end == s.span.start && is_begin(t, multiline))
+        })
+        .map(|t| t.span.clone())
+}
+
+/// カーソル位置の注記と、その注記が指し示す範囲を返す。
+/// 前方参照型注記なら装飾している文字列を、挟み込み型注記なら対になる注記を指す
+pub fn reference_target(
+    doc: &DocumentState,
+    offset: usize,
+) -> Option<(Range<usize>, Range<usize>)> {
+    let index = doc
+        .tokens
+        .iter()
+        .position(|t| t.span.start <= offset && offset < t.span.end)?;
+    let token = &doc.tokens[index];
+    let OwnedTokenKind::Annotation(annotation) = &token.kind else {
+        return None;
+    };
+    let target = match annotation {
+        OwnedAnnotation::BackRef { target, .. } => backref_target(doc, index, target),
+        OwnedAnnotation::SandwichedBegin { .. } => closing_partner(doc, token, false),
+        OwnedAnnotation::MultilineBegin { .. } => closing_partner(doc, token, true),
+        OwnedAnnotation::SandwichedEnd { .. } => opening_partner(doc, token, false),
+        OwnedAnnotation::MultilineEnd { .. } => opening_partner(doc, token, true),
+        _ => None,
+    }?;
+    Some((token.span.clone(), target))
+}
+
+/// 注記から、その装飾対象または対になる注記へ移動する
+pub fn compute_definition(
+    doc: &DocumentState,
+    uri: &Url,
+    pos: Position,
+) -> Option<GotoDefinitionResponse> {
+    let (_, target) = reference_target(doc, doc.offset_at_position(pos))?;
+    Some(GotoDefinitionResponse::Scalar(Location {
+        uri: uri.clone(),
+        range: to_range(doc, &target),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 本文中の`cursor`の位置から移動した先の文字列を返す
+    fn jump<'a>(doc: &'a DocumentState, cursor: &str) -> Option<&'a str> {
+        let offset = doc.text.find(cursor).unwrap();
+        let (_, target) = reference_target(doc, offset)?;
+        Some(&doc.text[target])
+    }
+
+    #[test]
+    fn backref_to_decorated_text() {
+        let doc = DocumentState::parse_body(
+            "吾輩は猫《ねこ》である［＃「ある」は太字］［＃「である」に傍点］\n",
+        );
+        let offset = doc.text.find("［＃「ある」").unwrap();
+        let (_, target) = reference_target(&doc, offset).unwrap();
+        assert_eq!(target.start, doc.text.find("ある［").unwrap());
+        assert_eq!(jump(&doc, "［＃「である」"), Some("である"));
+    }
+
+    #[test]
+    fn failed_backref_has_no_target() {
+        let doc = DocumentState::parse_body("吾輩は猫である［＃「犬」は太字］\n");
+        assert_eq!(jump(&doc, "［＃「犬」"), None);
+    }
+
+    #[test]
+    fn sandwiched_partners() {
+        let doc = DocumentState::parse_body(
+            "［＃太字］吾輩［＃太字終わり］は［＃斜体］猫［＃斜体終わり］\n",
+        );
+        let begin = doc.text.find("［＃太字］").unwrap();
+        let end = doc.text.find("［＃太字終わり］").unwrap();
+        assert_eq!(reference_target(&doc, begin).unwrap().1.start, end);
+        assert_eq!(reference_target(&doc, end).unwrap().1.start, begin);
+        assert_eq!(jump(&doc, "［＃斜体］"), Some("［＃斜体終わり］"));
+    }
+
+    #[test]
+    fn multiline_partners() {
+        let doc = DocumentState::parse_body(
+            "［＃ここから２字下げ］\n吾輩は猫である\n［＃ここで字下げ終わり］\n",
+        );
+        assert_eq!(jump(&doc, "［＃ここから"), Some("［＃ここで字下げ終わり］"));
+        assert_eq!(jump(&doc, "［＃ここで"), Some("［＃ここから２字下げ］"));
+    }
+
+    #[test]
+    fn unclosed_note_has_no_partner() {
+        let doc = DocumentState::parse_body("［＃太字］吾輩は猫である\n");
+        assert_eq!(jump(&doc, "［＃太字］"), None);
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use aozora_rs_core::{Notation, Severity, codes, lint_undeclared_notations};
+use aozora_rs_zip::ImgExtension;
+use tower_lsp::lsp_types::{
+    self, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    Url,
+};
+
+use crate::document::{DocumentState, OwnedAnnotation, OwnedTokenKind};
+use crate::document_link::{figure_refs, resolve_figure};
+use crate::gaiji::gaiji_notations;
+
+/// 診断の発行元として表示する名前
+const SOURCE: &str = "aozora-rs";
+
+pub fn to_range(doc: &DocumentState, span: &Range<usize>) -> lsp_types::Range {
+    lsp_types::Range {
+        start: doc.line_index.offset_to_position(&doc.text, span.start),
+        end: doc.line_index.offset_to_position(&doc.text, span.end),
+    }
+}
+
+/// aozora-rsの診断をLSPの診断に変換する。
+/// 位置情報を持たない診断は本文の先頭に報告する
+pub fn to_lsp_diagnostic(
+    doc: &DocumentState,
+    uri: &Url,
+    diagnostic: &aozora_rs_core::Diagnostic,
+) -> Diagnostic {
+    let span = diagnostic
+        .span
+        .clone()
+        .unwrap_or(doc.body_offset..doc.body_offset);
+    let related = diagnostic
+        .labels
+        .iter()
+        .map(|label| DiagnosticRelatedInformation {
+            location: Location::new(uri.clone(), to_range(doc, &label.span)),
+            message: label.message.text(),
+        })
+        .collect::<Vec<_>>();
+    Diagnostic {
+        range: to_range(doc, &span),
+        severity: Some(match diagnostic.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+        }),
+        code: Some(NumberOrString::String(diagnostic.code.to_string())),
+        source: Some(SOURCE.to_string()),
+        message: diagnostic.message.text(),
+        related_information: (!related.is_empty()).then_some(related),
+        ..Default::default()
+    }
+}
+
+fn warning(
+    doc: &DocumentState,
+    uri: &Url,
+    span: &Range<usize>,
+    code: &'static str,
+    args: Vec<String>,
+) -> Diagnostic {
+    let diagnostic =
+        aozora_rs_core::Diagnostic::new(code, Severity::Warning, args).with_span(span.clone());
+    to_lsp_diagnostic(doc, uri, &diagnostic)
+}
+
+/// ドキュメントの解析中に蓄積された警告をLSPの診断に変換する
+pub fn compute_diagnostics(doc: &DocumentState, uri: &Url) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    // 注記やルビの影響範囲の確定中に発生したエラー
+    for error in &doc.scopenize_errors {
+        diagnostics.push(to_lsp_diagnostic(doc, uri, &error.to_diagnostic(&doc.text)));
+    }
+
+    // 再トークン化のエラー
+    for error in &doc.retokenize_errors {
+        diagnostics.push(to_lsp_diagnostic(doc, uri, error));
+    }
+
+    // 解決できない外字注記
+    for notation in gaiji_notations(doc).iter().filter(|n| n.resolved.is_none()) {
+        diagnostics.push(warning(
+            doc,
+            uri,
+            &notation.span,
+            codes::UNRESOLVED_GAIJI,
+            vec![notation.description.clone()],
+        ));
+    }
+
+    // aozora-rsが認識できない注記
+    for token in &doc.tokens {
+        if let OwnedTokenKind::Annotation(OwnedAnnotation::Unknown(s)) = &token.kind {
+            diagnostics.push(warning(
+                doc,
+                uri,
+                &token.span,
+                codes::UNKNOWN_ANNOTATION,
+                vec![s.clone()],
+            ));
+        }
+    }
+
+    // 【テキスト中に現れる記号について】で宣言されていない記法
+    if !doc.meta.symbols.is_empty() {
+        for diagnostic in
+            lint_undeclared_notations(&doc.meta.declared_notations, used_notations(doc))
+        {
+            diagnostics.push(to_lsp_diagnostic(doc, uri, &diagnostic));
+        }
+    }
+
+    diagnostics.sort_by_key(|d| (d.range.start.line, d.range.start.character));
+    diagnostics
+}
+
+/// 本文で使われている記法とその位置を列挙する
+fn used_notations(doc: &DocumentState) -> impl Iterator<Item = (Notation, Range<usize>)> + '_ {
+    doc.tokens.iter().flat_map(|token| {
+        let start = token.span.start;
+        token
+            .notations
+            .iter()
+            .map(move |(notation, span)| (*notation, span.start + start..span.end + start))
+    })
+}
+
+/// 図の注記が参照する画像のうち、ファイルシステム上に見つからないパスを返す。
+/// ファイルの存在を確認するので、非同期の処理からは`spawn_blocking`で呼ぶ
+pub fn missing_figures(uri: &Url, paths: Vec<String>) -> HashSet<String> {
+    paths
+        .into_iter()
+        .filter(|path| {
+            // ファイルシステム上にない文書（未保存のバッファなど）は存在を確認できない
+            resolve_figure(uri, path)
+                .and_then(|u| u.to_file_path().ok())
+                .is_some_and(|file| !file.is_file())
+        })
+        .collect()
+}
+
+/// 図の注記が参照する画像を検査する。
+/// 画像の存在は`missing_figures`で確認した結果を使う
+pub fn compute_figure_diagnostics(
+    doc: &DocumentState,
+    uri: &Url,
+    missing: &HashSet<String>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for figure in figure_refs(doc) {
+        let extension = std::path::Path::new(&figure.path)
+            .extension()
+            .and_then(|e| e.to_str());
+        if extension.and_then(ImgExtension::from_extension).is_none() {
+            diagnostics.push(warning(
+                doc,
+                uri,
+                &figure.span,
+                codes::UNSUPPORTED_FIGURE,
+                vec![figure.path.clone()],
+            ));
+        }
+        if missing.contains(&figure.path) {
+            diagnostics.push(warning(
+                doc,
+                uri,
+                &figure.span,
+                codes::DEPENDENCY_NOT_FOUND,
+                vec![figure.path.clone()],
+            ));
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnose(body: &str) -> Vec<Diagnostic> {
+        let doc = DocumentState::parse_body(body);
+        compute_diagnostics(&doc, &Url::parse("file:///test.txt").unwrap())
+    }
+
+    fn code_of(d: &Diagnostic) -> &str {
+        match d.code.as_ref().unwrap() {
+            NumberOrString::String(s) => s,
+            NumberOrString::Number(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn no_diagnostics_for_valid_text() {
+        assert!(diagnose("［＃太字］吾輩［＃太字終わり］は猫である。\n").is_empty());
+    }
+
+    #[test]
+    fn isolated_end_note() {
+        let diagnostics = diagnose("吾輩は猫である。［＃太字終わり］\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(code_of(&diagnostics[0]), "AZ0005");
+        // 3行目（0始まりで2行目）の「吾輩は猫である。」の直後から始まる
+        assert_eq!(diagnostics[0].range.start.line, 2);
+        assert_eq!(diagnostics[0].range.start.character, 8);
+        assert_eq!(diagnostics[0].range.end.character, 16);
+    }
+
+    #[test]
+    fn crossing_note_labels() {
+        let diagnostics =
+            diagnose("［＃太字］吾輩［＃傍点］は猫［＃太字終わり］である［＃傍点終わり］\n");
+        let crossing = diagnostics.iter().find(|d| code_of(d) == "AZ0004").unwrap();
+        let related = crossing.related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[0].location.range.start.character, 7);
+        assert_eq!(related[1].location.range.start.character, 14);
+    }
+
+    #[test]
+    fn unknown_annotation() {
+        let diagnostics = diagnose("［＃存在しない注記］\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(code_of(&diagnostics[0]), "AZ0008");
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn gaiji_notation() {
+        assert!(diagnose("※［＃「木＋世」、第3水準1-85-56］\n").is_empty());
+
+        let diagnostics = diagnose("※［＃「存在しない字」］\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(code_of(&diagnostics[0]), "AZ0009");
+        assert_eq!(diagnostics[0].range.start.character, 0);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn figure_files() {
+        let dir = std::env::temp_dir().join(format!("aozora-lsp-figure-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("fig1.png"), b"").unwrap();
+        std::fs::write(dir.join("fig2.bmp"), b"").unwrap();
+        let uri = Url::from_file_path(dir.join("test.txt")).unwrap();
+
+        let body = "［＃挿絵（fig1.png、横32×縦24）入る］\n［＃挿絵（fig2.bmp、横32×縦24）入る］\n［＃挿絵（fig3.png、横32×縦24）入る］\n";
+        let doc = DocumentState::parse_body(body);
+        let paths = figure_refs(&doc).into_iter().map(|f| f.path).collect();
+        let missing = missing_figures(&uri, paths);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let diagnostics = compute_figure_diagnostics(&doc, &uri, &missing);
+
+        let codes = diagnostics.iter().map(code_of).collect::<Vec<_>>();
+        assert_eq!(codes, ["AZ0011", "AZ0010"]);
+        assert_eq!(diagnostics[0].range.start.line, 3);
+        assert_eq!(diagnostics[1].range.start.line, 4);
+    }
+
+    #[test]
+    fn undeclared_notation() {
+        let body = "\n-------------------------------------------------------\n【テキスト中に現れる記号について】\n\n《》：ルビ\n（例）吾輩《わがはい》\n-------------------------------------------------------\n\n吾輩《わがはい》は〔cafe'〕の｜猫《ねこ》\n";
+        let doc = DocumentState::parse_body(body);
+        let diagnostics = compute_diagnostics(&doc, &Url::parse("file:///test.txt").unwrap());
+        let codes: Vec<_> = diagnostics.iter().map(code_of).collect();
+        assert_eq!(codes, ["AZ0012", "AZ0012"]);
+        assert!(diagnostics[0].message.contains("〔〕"));
+        assert!(diagnostics[1].message.contains("｜"));
+    }
+}
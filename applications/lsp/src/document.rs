@@ -1,10 +1,11 @@
 use std::ops::Range;
 
 use aozora_rs_core::{
-    Annotation, AozoraTokenKind, BackRefKind, Deco, MultiLine, PageDef,
-    Sandwiched, Scope, Single, Tokenized, WholeLine,
-    parse_meta, scopenize, tokenize,
+    Annotation, AozoraTokenKind, BackRefKind, Deco, Diagnostic, MultiLine, Notation, PageDef,
+    Sandwiched, Scope, ScopenizeError, Single, Tokenized, WholeLine, declared_notations,
+    parse_meta, resolve_gaiji, retokenize, scopenize, tokenize, tokenize_one, used_notations,
 };
+use aozora_rs_gaiji::resolve_chuki;
 use tower_lsp::lsp_types::Position;
 use winnow::LocatingSlice;
 
@@ -14,17 +15,72 @@ use crate::line_index::LineIndex;
 pub struct OwnedMeta {
     pub title: String,
     pub author: String,
+    pub subtitle: Option<String>,
+    pub original_title: Option<String>,
+    pub translator: Option<String>,
+    pub editor: Option<String>,
+    /// 【テキスト中に現れる記号について】に記述された記号
+    pub symbols: Vec<OwnedSymbolEntry>,
+    /// 【テキスト中に現れる記号について】で宣言されている記法
+    pub declared_notations: Vec<Notation>,
+    /// 見出しの各行の種類と範囲（テキストに現れる順）
+    pub header_fields: Vec<(HeaderField, Range<usize>)>,
+}
+
+/// 所有型の記号の説明
+pub struct OwnedSymbolEntry {
+    pub symbol: String,
+    pub explanation: Vec<String>,
+    pub examples: Vec<String>,
+    pub span: Range<usize>,
+}
+
+/// 見出しの行の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderField {
+    Title,
+    Subtitle,
+    OriginalTitle,
+    Author,
+    Translator,
+    Editor,
+}
+
+impl HeaderField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Title => "タイトル",
+            Self::Subtitle => "副題",
+            Self::OriginalTitle => "原題",
+            Self::Author => "著者",
+            Self::Translator => "翻訳者",
+            Self::Editor => "編者",
+        }
+    }
+
+    /// 作品名に関する行であるか
+    pub fn is_title(&self) -> bool {
+        matches!(self, Self::Title | Self::Subtitle | Self::OriginalTitle)
+    }
 }
 
 /// 所有型のトークン
 pub struct OwnedToken {
     pub kind: OwnedTokenKind,
     pub span: Range<usize>,
+    /// トークン中で使われている記法と、トークンの先頭からの位置
+    pub notations: Vec<(Notation, Range<usize>)>,
 }
 
 /// トークン種別の所有型表現
 pub enum OwnedTokenKind {
     Annotation(OwnedAnnotation),
+    /// `description`は注記の中身（`［＃`と`］`の間）、`resolved`は解決できた場合のUnicode文字列
+    Gaiji {
+        description: String,
+        menkuten: Option<(u8, u8, u8)>,
+        resolved: Option<String>,
+    },
     Ruby(String),
     RubyDelimiter,
     Text,
@@ -33,17 +89,29 @@ pub enum OwnedTokenKind {
 
 /// 注記の所有型表現
 pub enum OwnedAnnotation {
-    BackRef { description: String },
+    BackRef { description: String, target: String },
     SandwichedBegin { description: String },
     SandwichedEnd { description: String },
     MultilineBegin { description: String },
     MultilineEnd { description: String },
-    Single { description: String },
+    /// `figure`は図の注記であれば画像のパス、`kanbun`は訓点や送り仮名であるか
+    Single {
+        description: String,
+        figure: Option<OwnedFigure>,
+        kanbun: bool,
+    },
     WholeLine { description: String },
     PageDef { description: String },
     Unknown(String),
 }
 
+/// 図の注記に書かれた画像のパス
+pub struct OwnedFigure {
+    pub path: String,
+    /// 注記の先頭からパスの先頭までのバイト数
+    pub offset: usize,
+}
+
 /// 所有型のスコープ
 pub struct OwnedScope {
     pub deco_description: String,
@@ -51,16 +119,30 @@ pub struct OwnedScope {
     pub span: Range<usize>,
 }
 
-/// 折り畳みや見出し判定に使うDecoの分類
+/// 折り畳みや見出し判定、セマンティックトークンに使うDecoの分類
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum OwnedDecoKind {
+    /// 字下げ、地付き、地上げ
+    Indent,
+    /// 字下げ以外のブロック（文字の大きさ、字詰め）
     Block,
     HeadA,
     HeadB,
     HeadC,
+    /// ルビの親文字
+    Ruby,
+    Boten,
+    Bosen,
     Inline,
 }
 
+impl OwnedDecoKind {
+    /// 段落単位で適用される装飾か
+    pub fn is_block(self) -> bool {
+        matches!(self, Self::Indent | Self::Block)
+    }
+}
+
 /// ドキュメント全体の解析状態
 pub struct DocumentState {
     pub text: String,
@@ -69,14 +151,21 @@ pub struct DocumentState {
     pub symbol_block: Option<Range<usize>>,
     pub tokens: Vec<OwnedToken>,
     pub scopes: Vec<OwnedScope>,
+    pub scopenize_errors: Vec<ScopenizeError>,
+    /// 再トークン化のエラーは装飾を借用するので、診断に変換して保持する
+    pub retokenize_errors: Vec<Diagnostic>,
     pub line_index: LineIndex,
 }
 
-fn describe_annotation(annotation: &Annotation<'_>) -> OwnedAnnotation {
+/// `raw`は注記の書かれた部分の原文
+fn describe_annotation(annotation: &Annotation<'_>, raw: &str) -> OwnedAnnotation {
     match annotation {
         Annotation::BackRef(b) => {
             let desc = format!("「{}」を{}にします", b.range.0, describe_backref_kind(&b.kind));
-            OwnedAnnotation::BackRef { description: desc }
+            OwnedAnnotation::BackRef {
+                description: desc,
+                target: b.range.0.to_string(),
+            }
         }
         Annotation::Sandwiched(s) => match s {
             Sandwiched::Begin(b) => {
@@ -100,7 +189,19 @@ fn describe_annotation(annotation: &Annotation<'_>) -> OwnedAnnotation {
         },
         Annotation::Single(s) => {
             let desc = describe_single(s);
-            OwnedAnnotation::Single { description: desc }
+            let figure = match s {
+                // パスは原文を借用しているので、原文の中での位置を求められる
+                Single::Figure(f) => Some(OwnedFigure {
+                    path: f.path.to_string(),
+                    offset: f.path.as_ptr() as usize - raw.as_ptr() as usize,
+                }),
+                _ => None,
+            };
+            OwnedAnnotation::Single {
+                description: desc,
+                figure,
+                kanbun: matches!(s, Single::Kundoku(_) | Single::Okurigana(_)),
+            }
         }
         Annotation::WholeLine(w) => {
             let desc = describe_wholeline(w);
@@ -225,27 +326,46 @@ fn describe_pagedef(p: &PageDef) -> String {
     }
 }
 
-fn convert_token(token: &Tokenized<'_>) -> OwnedToken {
+/// `source`はトークン化した文字列
+fn convert_token(token: &Tokenized<'_>, source: &str) -> OwnedToken {
     let kind = match &token.kind {
-        AozoraTokenKind::Annotation(a) => OwnedTokenKind::Annotation(describe_annotation(a)),
+        AozoraTokenKind::Annotation(a) => {
+            OwnedTokenKind::Annotation(describe_annotation(a, &source[token.span.clone()]))
+        }
+        AozoraTokenKind::Gaiji(g) => OwnedTokenKind::Gaiji {
+            description: g.chuki.to_string(),
+            menkuten: g.parsed.sjis,
+            resolved: g.resolved.as_deref().map(str::to_string),
+        },
         AozoraTokenKind::Ruby(r) => OwnedTokenKind::Ruby(r.to_string()),
         AozoraTokenKind::RubyDelimiter => OwnedTokenKind::RubyDelimiter,
         AozoraTokenKind::Text(_) => OwnedTokenKind::Text,
         AozoraTokenKind::Br => OwnedTokenKind::Br,
     };
+    let start = token.span.start;
+    let notations = used_notations(std::slice::from_ref(token))
+        .into_iter()
+        .map(|(notation, span)| (notation, span.start - start..span.end - start))
+        .collect();
     OwnedToken {
         kind,
         span: token.span.clone(),
+        notations,
     }
 }
 
 fn classify_deco(deco: &Deco<'_>) -> OwnedDecoKind {
     match deco {
-        Deco::Indent(_) | Deco::Hanging(_) | Deco::Grounded | Deco::LowFlying(_)
-        | Deco::Smaller(_) | Deco::Bigger(_) | Deco::Kerning(_) => OwnedDecoKind::Block,
+        Deco::Indent(_) | Deco::Hanging(_) | Deco::Grounded | Deco::LowFlying(_) => {
+            OwnedDecoKind::Indent
+        }
+        Deco::Smaller(_) | Deco::Bigger(_) | Deco::Kerning(_) => OwnedDecoKind::Block,
         Deco::AHead => OwnedDecoKind::HeadA,
         Deco::BHead => OwnedDecoKind::HeadB,
         Deco::CHead => OwnedDecoKind::HeadC,
+        Deco::Ruby(_) => OwnedDecoKind::Ruby,
+        Deco::Boten(_) => OwnedDecoKind::Boten,
+        Deco::Bosen(_) => OwnedDecoKind::Bosen,
         _ => OwnedDecoKind::Inline,
     }
 }
@@ -258,6 +378,75 @@ fn convert_scope(scope: &Scope<'_>) -> OwnedScope {
     }
 }
 
+fn with_span(error: ScopenizeError, span: Range<usize>) -> ScopenizeError {
+    match error {
+        ScopenizeError::UnclosedInlineNote(_) => ScopenizeError::UnclosedInlineNote(span),
+        ScopenizeError::BackRefFailed(_) => ScopenizeError::BackRefFailed(span),
+        ScopenizeError::InvalidRubyDelimiterUsage(_) => {
+            ScopenizeError::InvalidRubyDelimiterUsage(span)
+        }
+        ScopenizeError::CrossingNote(_) => ScopenizeError::CrossingNote(span),
+        ScopenizeError::IsolatedEndNote(_) => ScopenizeError::IsolatedEndNote(span),
+    }
+}
+
+fn shift_span(span: &Range<usize>, delta: isize) -> Range<usize> {
+    ((span.start as isize + delta) as usize)..((span.end as isize + delta) as usize)
+}
+
+/// 再解析する段落に対して、既存のスパンがどの位置にあるか
+enum Placement {
+    /// 段落より前にあり、そのまま使える
+    Before,
+    /// 段落より後ろにあり、編集量だけずらせば使える
+    After,
+    /// 段落を包んでおり、終端だけをずらせば使える
+    Enclosing,
+    /// 段落の中にあり、再解析の結果で置き換える
+    Inside,
+    /// 段落の境界をまたいでいるので再利用できない
+    Crossing,
+}
+
+fn place(span: &Range<usize>, paragraph: &Range<usize>) -> Placement {
+    if paragraph.start <= span.start && span.end <= paragraph.end {
+        Placement::Inside
+    } else if span.end <= paragraph.start {
+        Placement::Before
+    } else if paragraph.end <= span.start {
+        Placement::After
+    } else if span.start <= paragraph.start && paragraph.end <= span.end {
+        Placement::Enclosing
+    } else {
+        Placement::Crossing
+    }
+}
+
+/// 既存のスパンを段落の再解析後の位置に合わせる。再利用できない場合はNoneを返す。
+fn relocate(
+    span: &Range<usize>,
+    paragraph: &Range<usize>,
+    delta: isize,
+) -> Option<Option<Range<usize>>> {
+    match place(span, paragraph) {
+        Placement::Before => Some(Some(span.clone())),
+        Placement::After => Some(Some(shift_span(span, delta))),
+        Placement::Enclosing => Some(Some(span.start..((span.end as isize + delta) as usize))),
+        Placement::Inside => Some(None),
+        Placement::Crossing => None,
+    }
+}
+
+/// 行をまたいで他の行の解釈を変えうるトークンかどうか
+fn is_multiline_annotation(token: &OwnedToken) -> bool {
+    matches!(
+        token.kind,
+        OwnedTokenKind::Annotation(
+            OwnedAnnotation::MultilineBegin { .. } | OwnedAnnotation::MultilineEnd { .. }
+        )
+    )
+}
+
 fn detect_symbol_block(text: &str, body_offset: usize) -> Option<Range<usize>> {
     let header = &text[..body_offset];
     let separator = "-------------------------------------------------------";
@@ -268,57 +457,316 @@ fn detect_symbol_block(text: &str, body_offset: usize) -> Option<Range<usize>> {
     Some(first..end)
 }
 
+/// テストで本文の前に置く、タイトルと著者だけの見出し
+#[cfg(test)]
+pub const TEST_HEADER: &str = "タイトル\n著者\n";
+
 impl DocumentState {
+    /// 見出しの各行の種類と、行の範囲を返す
+    pub fn header_fields(&self) -> &[(HeaderField, Range<usize>)] {
+        &self.meta.header_fields
+    }
+
+    /// テスト用に、[`TEST_HEADER`]の見出しに`body`を続けたテキストを解析する
+    #[cfg(test)]
+    pub fn parse_body(body: &str) -> Self {
+        Self::parse(format!("{}{}", TEST_HEADER, body)).unwrap()
+    }
+
     /// テキストを解析してDocumentStateを構築する。
-    /// メタデータ解析に失敗した場合はNoneを返す。
-    pub fn parse(text: String) -> Option<Self> {
+    /// メタデータ解析に失敗した場合は増分同期を続けられるよう元のテキストをErrで返す。
+    pub fn parse(text: String) -> Result<Self, String> {
         let line_index = LineIndex::new(&text);
 
         let mut cursor = text.as_str();
-        let meta = parse_meta(&mut cursor).ok()?;
+        let Ok(meta) = parse_meta(&mut cursor) else {
+            return Err(text);
+        };
         let body_offset = text.len() - cursor.len();
 
+        let spans = &meta.spans;
+        let mut header_fields: Vec<(HeaderField, Range<usize>)> = [
+            (HeaderField::Title, Some(spans.title.clone())),
+            (HeaderField::Subtitle, spans.subtitle.clone()),
+            (HeaderField::OriginalTitle, spans.original_title.clone()),
+            (HeaderField::Author, Some(spans.author.clone())),
+            (HeaderField::Translator, spans.translator.clone()),
+            (HeaderField::Editor, spans.editor.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(field, span)| Some((field, span?)))
+        .collect();
+        header_fields.sort_by_key(|(_, span)| span.start);
+
         let owned_meta = OwnedMeta {
             title: meta.title.to_string(),
             author: meta.author.to_string(),
+            subtitle: meta.subtitle.map(str::to_string),
+            original_title: meta.original_title.map(str::to_string),
+            translator: meta.translator.map(str::to_string),
+            editor: meta.editor.map(str::to_string),
+            declared_notations: declared_notations(&meta.symbols),
+            header_fields,
+            symbols: meta
+                .symbols
+                .iter()
+                .map(|e| OwnedSymbolEntry {
+                    symbol: e.symbol.to_string(),
+                    explanation: e.explanation.iter().map(|l| l.to_string()).collect(),
+                    examples: e.examples.iter().map(|l| l.to_string()).collect(),
+                    span: e.span.clone(),
+                })
+                .collect(),
         };
 
         let symbol_block = detect_symbol_block(&text, body_offset);
 
         let mut loc = LocatingSlice::new(cursor);
-        let tokenized = tokenize(&mut loc).ok()?;
+        let Ok(mut tokenized) = tokenize(&mut loc) else {
+            return Err(text);
+        };
+        resolve_gaiji(&mut tokenized, |c| resolve_chuki(c.clone()));
 
         let owned_tokens: Vec<OwnedToken> = tokenized
             .iter()
             .map(|t| {
-                let mut ot = convert_token(t);
-                ot.span = (ot.span.start + body_offset)..(ot.span.end + body_offset);
+                let mut ot = convert_token(t, cursor);
+                ot.span = shift_span(&ot.span, body_offset as isize);
                 ot
             })
             .collect();
 
-        let ((scopes, _expressions), _errors) = scopenize(tokenized).into_tuple();
+        let ((scopes, expressions), errors) = scopenize(tokenized).into_tuple();
 
         let owned_scopes: Vec<OwnedScope> = scopes
             .iter()
             .map(|s| {
                 let mut os = convert_scope(s);
-                os.span = (os.span.start + body_offset)..(os.span.end + body_offset);
+                os.span = shift_span(&os.span, body_offset as isize);
                 os
             })
             .collect();
 
-        Some(DocumentState {
+        let scopenize_errors: Vec<ScopenizeError> = errors
+            .into_iter()
+            .map(|e| {
+                let span = shift_span(e.span(), body_offset as isize);
+                with_span(e, span)
+            })
+            .collect();
+
+        let (_pages, errors) = retokenize(expressions, scopes);
+        let retokenize_errors: Vec<Diagnostic> = errors
+            .iter()
+            .map(|e| {
+                let mut d = e.to_diagnostic();
+                d.span = d.span.map(|s| shift_span(&s, body_offset as isize));
+                d
+            })
+            .collect();
+
+        Ok(DocumentState {
             text,
             meta: owned_meta,
             body_offset,
             symbol_block,
             tokens: owned_tokens,
             scopes: owned_scopes,
+            scopenize_errors,
+            retokenize_errors,
             line_index,
         })
     }
 
+    /// 増分同期の変更を1件適用する。`range`がNoneの場合は全文の置き換えとして扱う。
+    ///
+    /// 編集された行だけを再トークン化し、影響を受けないトークンやスコープは
+    /// スパンをずらして再利用する。行をまたぐ注記などで局所的な再解析が
+    /// できない場合は全体を解析し直す。
+    /// メタデータ解析に失敗した場合は編集後の本文をErrで返し、自身は使えない状態になる。
+    pub fn apply_change(
+        &mut self,
+        range: Option<tower_lsp::lsp_types::Range>,
+        new_text: &str,
+    ) -> Result<(), String> {
+        let Some(range) = range else {
+            *self = Self::parse(new_text.to_string())?;
+            return Ok(());
+        };
+        let edit = self.line_index.range_to_span(&self.text, range);
+        let old_text = self.text[edit.clone()].to_string();
+        self.text.replace_range(edit.clone(), new_text);
+        self.line_index.apply_edit(edit.clone(), new_text);
+
+        if self
+            .reparse_incrementally(edit, &old_text, new_text)
+            .is_none()
+        {
+            *self = Self::parse(std::mem::take(&mut self.text))?;
+        }
+        Ok(())
+    }
+
+    /// 編集箇所を含む行だけを解析し直す。局所的に解析できない場合はNoneを返す。
+    fn reparse_incrementally(
+        &mut self,
+        edit: Range<usize>,
+        old_text: &str,
+        new_text: &str,
+    ) -> Option<()> {
+        // メタデータ部分の編集は本文の開始位置を変えうる
+        if edit.start < self.body_offset {
+            return None;
+        }
+        // 記号についての区切り線がまだ無い場合、本文中の区切り線がメタデータとして解釈されうる
+        if self.symbol_block.is_none() && (old_text.contains('-') || new_text.contains('-')) {
+            return None;
+        }
+        let delta = new_text.len() as isize - old_text.len() as isize;
+        let edit_end = edit.start + new_text.len();
+
+        // 編集箇所を含む行の先頭から切り出し直す。行頭をまたぐトークンがあればその行まで戻る
+        let line_start = |offset: usize| {
+            self.text[..offset]
+                .rfind('\n')
+                .map(|i| i + 1)
+                .unwrap_or(0)
+                .max(self.body_offset)
+        };
+        let mut start = line_start(edit.start);
+        while let Some(token) = self
+            .tokens
+            .iter()
+            .find(|t| t.span.start < start && start < t.span.end)
+        {
+            start = line_start(token.span.start);
+        }
+        // 前の行で閉じられていない括弧は、編集で挿入された閉じ括弧と対応しうる
+        if [old_text, new_text]
+            .iter()
+            .any(|t| t.contains(['《', '》', '［', '］']))
+            && [('《', '》'), ('［', '］')].iter().any(|&(open, close)| {
+                let before = &self.text[self.body_offset..start];
+                before
+                    .rfind(open)
+                    .is_some_and(|i| !before[i..].contains(close))
+            })
+        {
+            return None;
+        }
+        let first = self.tokens.partition_point(|t| t.span.start < start);
+        // 行末のルビ区切りは次の行のトークンまで読み進めてしまう
+        if self.tokens[first.saturating_sub(3)..first]
+            .iter()
+            .any(|t| matches!(t.kind, OwnedTokenKind::RubyDelimiter))
+        {
+            return None;
+        }
+
+        // 編集箇所より後ろで、改行の直後かつ既存のトークンの境界に
+        // 再び一致するまで1トークンずつ切り出す
+        let mut input = LocatingSlice::new(&self.text[start..]);
+        let mut tokenized = Vec::new();
+        let last = loop {
+            let Ok(token) = tokenize_one(&mut input) else {
+                if input.is_empty() {
+                    break self.tokens.len();
+                }
+                return None;
+            };
+            let end = start + token.span.end;
+            let is_br = matches!(token.kind, AozoraTokenKind::Br);
+            tokenized.push(token);
+            if is_br && end > edit_end {
+                let old_end = (end as isize - delta) as usize;
+                let index = self.tokens.partition_point(|t| t.span.start < old_end);
+                if self
+                    .tokens
+                    .get(index)
+                    .is_some_and(|t| t.span.start == old_end)
+                {
+                    break index;
+                }
+            }
+        };
+        let old_end = self
+            .tokens
+            .get(last)
+            .map(|t| t.span.start)
+            .unwrap_or((self.text.len() as isize - delta) as usize);
+        let paragraph = start..old_end;
+
+        // 複数行にわたる注記の対応付けは全体の解析に任せる
+        let is_multiline = |t: &Tokenized<'_>| {
+            matches!(
+                t.kind,
+                AozoraTokenKind::Annotation(Annotation::Multiline(_))
+            )
+        };
+        if self.tokens[first..last].iter().any(is_multiline_annotation)
+            || tokenized.iter().any(is_multiline)
+        {
+            return None;
+        }
+        resolve_gaiji(&mut tokenized, |c| resolve_chuki(c.clone()));
+        let new_tokens: Vec<OwnedToken> = tokenized
+            .iter()
+            .map(|t| {
+                let mut ot = convert_token(t, &self.text[start..]);
+                ot.span = shift_span(&ot.span, start as isize);
+                ot
+            })
+            .collect();
+
+        // ルビ区切りの誤用は後続の行のトークンまで読み進めてしまうので全体の解析に任せる
+        let ((scopes, expressions), errors) = scopenize(tokenized).into_tuple();
+        if errors
+            .iter()
+            .any(|e| matches!(e, ScopenizeError::InvalidRubyDelimiterUsage(_)))
+        {
+            return None;
+        }
+        let new_scopes: Vec<OwnedScope> = scopes
+            .iter()
+            .map(|s| {
+                let mut os = convert_scope(s);
+                os.span = shift_span(&os.span, start as isize);
+                os
+            })
+            .collect();
+        if !self.retokenize_errors.is_empty() || !retokenize(expressions, scopes).1.is_empty() {
+            return None;
+        }
+
+        let mut scopes = Vec::with_capacity(self.scopes.len() + new_scopes.len());
+        for mut scope in std::mem::take(&mut self.scopes) {
+            if let Some(span) = relocate(&scope.span, &paragraph, delta)? {
+                scope.span = span;
+                scopes.push(scope);
+            }
+        }
+        scopes.extend(new_scopes);
+
+        let mut scopenize_errors = Vec::with_capacity(self.scopenize_errors.len());
+        for error in std::mem::take(&mut self.scopenize_errors) {
+            if let Some(span) = relocate(error.span(), &paragraph, delta)? {
+                scopenize_errors.push(with_span(error, span));
+            }
+        }
+        scopenize_errors.extend(errors.into_iter().map(|e| {
+            let span = shift_span(e.span(), start as isize);
+            with_span(e, span)
+        }));
+
+        for token in &mut self.tokens[last..] {
+            token.span = shift_span(&token.span, delta);
+        }
+        self.tokens.splice(first..last, new_tokens);
+        self.scopes = scopes;
+        self.scopenize_errors = scopenize_errors;
+        Some(())
+    }
+
     pub fn token_at_offset(&self, offset: usize) -> Option<&OwnedToken> {
         self.tokens
             .iter()
@@ -336,3 +784,157 @@ impl DocumentState {
         self.line_index.position_to_offset(&self.text, pos)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Snapshot = (
+        Vec<(Range<usize>, String, std::mem::Discriminant<OwnedTokenKind>)>,
+        Vec<(Range<usize>, String)>,
+        Vec<(Range<usize>, &'static str)>,
+    );
+
+    fn snapshot(doc: &DocumentState) -> Snapshot {
+        let tokens = doc
+            .tokens
+            .iter()
+            .map(|t| {
+                let text = doc.text[t.span.clone()].to_string();
+                (t.span.clone(), text, std::mem::discriminant(&t.kind))
+            })
+            .collect();
+        let mut scopes: Vec<_> = doc
+            .scopes
+            .iter()
+            .map(|s| (s.span.clone(), s.deco_description.clone()))
+            .collect();
+        scopes.sort_by_key(|(span, desc)| (span.start, span.end, desc.clone()));
+        let mut errors: Vec<_> = doc
+            .scopenize_errors
+            .iter()
+            .map(|e| (e.span().clone(), e.code()))
+            .collect();
+        errors.sort_by_key(|(span, _)| (span.start, span.end));
+        (tokens, scopes, errors)
+    }
+
+    /// 増分解析の結果が全文の再解析と一致することを確かめる
+    fn assert_incremental(text: &str, edit: Range<usize>, new_text: &str) {
+        let mut incremental = DocumentState::parse(text.to_string()).unwrap();
+        let range = tower_lsp::lsp_types::Range {
+            start: incremental
+                .line_index
+                .offset_to_position(&incremental.text, edit.start),
+            end: incremental
+                .line_index
+                .offset_to_position(&incremental.text, edit.end),
+        };
+        incremental.apply_change(Some(range), new_text).unwrap();
+
+        let mut expected_text = text.to_string();
+        expected_text.replace_range(edit, new_text);
+        let full = DocumentState::parse(expected_text.clone()).unwrap();
+
+        assert_eq!(incremental.text, expected_text);
+        assert_eq!(snapshot(&incremental), snapshot(&full));
+        for offset in 0..=expected_text.len() {
+            if expected_text.is_char_boundary(offset) {
+                assert_eq!(
+                    incremental
+                        .line_index
+                        .offset_to_position(&incremental.text, offset),
+                    full.line_index.offset_to_position(&full.text, offset)
+                );
+            }
+        }
+    }
+
+    const TEXT: &str = "吾輩は猫である\n夏目漱石\n\n［＃ここから２字下げ］\n吾輩は猫《ねこ》である。\n［＃ここで字下げ終わり］\n名前はまだ無い。［＃「無い」に傍点］\n［＃太字］どこで［＃太字終わり］生れたか\n";
+
+    fn offset_of(pattern: &str) -> usize {
+        TEXT.find(pattern).unwrap()
+    }
+
+    #[test]
+    fn insert_plain_text() {
+        let at = offset_of("名前");
+        assert_incremental(TEXT, at..at, "猫の");
+    }
+
+    #[test]
+    fn insert_newline_inside_block() {
+        let at = offset_of("である。");
+        assert_incremental(TEXT, at..at, "\n");
+    }
+
+    #[test]
+    fn break_backref() {
+        let at = offset_of("無い」");
+        assert_incremental(TEXT, at..at + "無い".len(), "有る");
+    }
+
+    #[test]
+    fn remove_sandwiched_end() {
+        let at = offset_of("［＃太字終わり］");
+        assert_incremental(TEXT, at..at + "［＃太字終わり］".len(), "");
+    }
+
+    #[test]
+    fn remove_multiline_end() {
+        let at = offset_of("［＃ここで字下げ終わり］");
+        assert_incremental(TEXT, at..at + "［＃ここで字下げ終わり］".len(), "");
+    }
+
+    #[test]
+    fn open_bracket_across_lines() {
+        let at = offset_of("名前");
+        assert_incremental(TEXT, at..at, "《");
+        let at = offset_of("どこで");
+        assert_incremental(TEXT, at..at, "》");
+    }
+
+    #[test]
+    fn close_bracket_opened_on_previous_line() {
+        let text = "題\n著者\n\n吾輩《ねこ\nである\n";
+        let at = text.find("である").unwrap() + "である".len();
+        assert_incremental(text, at..at, "》");
+        let text = "題\n著者\n\n吾輩［＃「吾輩\nは太字\n";
+        let at = text.find("は太字").unwrap() + "は太字".len();
+        assert_incremental(text, at..at, "］");
+    }
+
+    #[test]
+    fn join_lines() {
+        let at = offset_of("\n［＃太字］");
+        assert_incremental(TEXT, at..at + 1, "");
+    }
+
+    #[test]
+    fn edit_header() {
+        let at = offset_of("夏目漱石");
+        assert_incremental(TEXT, at..at + "夏目漱石".len(), "夏目金之助");
+    }
+
+    #[test]
+    fn header_fields() {
+        let doc = DocumentState::parse(
+            "最後の一葉\nThe Last Leaf\nオー・ヘンリー\n結城浩訳\n\n本文\n".to_string(),
+        )
+        .unwrap();
+        let fields: Vec<_> = doc
+            .header_fields()
+            .iter()
+            .map(|(field, span)| (*field, &doc.text[span.clone()]))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (HeaderField::Title, "最後の一葉"),
+                (HeaderField::OriginalTitle, "The Last Leaf"),
+                (HeaderField::Author, "オー・ヘンリー"),
+                (HeaderField::Translator, "結城浩訳"),
+            ]
+        );
+    }
+}
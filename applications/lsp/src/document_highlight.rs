@@ -0,0 +1,39 @@
+use tower_lsp::lsp_types::{DocumentHighlight, DocumentHighlightKind, Position};
+
+use crate::definition::reference_target;
+use crate::diagnostics::to_range;
+use crate::document::DocumentState;
+
+/// カーソル位置の注記と、その装飾対象または対になる注記を強調表示する
+pub fn compute_document_highlights(
+    doc: &DocumentState,
+    pos: Position,
+) -> Option<Vec<DocumentHighlight>> {
+    let (origin, target) = reference_target(doc, doc.offset_at_position(pos))?;
+    Some(
+        [origin, target]
+            .iter()
+            .map(|span| DocumentHighlight {
+                range: to_range(doc, span),
+                kind: Some(DocumentHighlightKind::TEXT),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_annotation_and_target() {
+        let doc = DocumentState::parse_body("吾輩は猫［＃「猫」は太字］である\n");
+        let highlights = compute_document_highlights(&doc, Position::new(2, 6)).unwrap();
+
+        assert_eq!(highlights.len(), 2);
+        assert_eq!(highlights[0].range.start, Position::new(2, 4));
+        assert_eq!(highlights[1].range.start, Position::new(2, 3));
+        assert_eq!(highlights[1].range.end, Position::new(2, 4));
+        assert!(compute_document_highlights(&doc, Position::new(2, 0)).is_none());
+    }
+}
@@ -0,0 +1,72 @@
+use std::ops::Range;
+
+use tower_lsp::lsp_types::{DocumentLink, Url};
+
+use crate::diagnostics::to_range;
+use crate::document::{DocumentState, OwnedAnnotation, OwnedTokenKind};
+
+/// 図の注記に書かれた画像のパス
+pub struct FigureRef {
+    /// パスの部分だけを指す範囲
+    pub span: Range<usize>,
+    pub path: String,
+}
+
+/// 本文中の図の注記を列挙する
+pub fn figure_refs(doc: &DocumentState) -> Vec<FigureRef> {
+    doc.tokens
+        .iter()
+        .filter_map(|token| {
+            let OwnedTokenKind::Annotation(OwnedAnnotation::Single {
+                figure: Some(figure),
+                ..
+            }) = &token.kind
+            else {
+                return None;
+            };
+            let start = token.span.start + figure.offset;
+            Some(FigureRef {
+                span: start..start + figure.path.len(),
+                path: figure.path.clone(),
+            })
+        })
+        .collect()
+}
+
+/// 図のパスはテキストファイルからの相対パスとして解決する
+pub fn resolve_figure(uri: &Url, path: &str) -> Option<Url> {
+    uri.join(path).ok()
+}
+
+/// 図の注記のパスから画像を開けるようにする
+pub fn compute_document_links(doc: &DocumentState, uri: &Url) -> Vec<DocumentLink> {
+    figure_refs(doc)
+        .into_iter()
+        .map(|figure| DocumentLink {
+            range: to_range(doc, &figure.span),
+            target: resolve_figure(uri, &figure.path),
+            tooltip: Some("画像を開く".to_string()),
+            data: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_figure_path() {
+        let doc = DocumentState::parse_body("［＃挿絵（images/fig1.png、横320×縦240）入る］\n");
+        let uri = Url::parse("file:///books/test.txt").unwrap();
+        let links = compute_document_links(&doc, &uri);
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].range.start.character, 5);
+        assert_eq!(links[0].range.end.character, 20);
+        assert_eq!(
+            links[0].target.as_ref().unwrap().as_str(),
+            "file:///books/images/fig1.png"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use tower_lsp::lsp_types::{DocumentSymbol, Range, SymbolKind};
+
+use crate::document::{DocumentState, OwnedDecoKind, OwnedTokenKind};
+
+/// 見出しの階層（大見出しが最上位）
+fn heading_level(kind: OwnedDecoKind) -> Option<u8> {
+    match kind {
+        OwnedDecoKind::HeadA => Some(1),
+        OwnedDecoKind::HeadB => Some(2),
+        OwnedDecoKind::HeadC => Some(3),
+        _ => None,
+    }
+}
+
+fn heading_detail(level: u8) -> &'static str {
+    match level {
+        1 => "大見出し",
+        2 => "中見出し",
+        _ => "小見出し",
+    }
+}
+
+/// 見出しの範囲に含まれる本文だけを繋げて見出し名にする（ルビや注記は除く）
+fn heading_name(doc: &DocumentState, span: &std::ops::Range<usize>) -> String {
+    let name: String = doc
+        .tokens
+        .iter()
+        .filter(|t| matches!(t.kind, OwnedTokenKind::Text))
+        .filter(|t| span.start <= t.span.start && t.span.end <= span.end)
+        .map(|t| &doc.text[t.span.clone()])
+        .collect();
+    let name = name.trim();
+    let name = if name.is_empty() {
+        doc.text[span.clone()].trim()
+    } else {
+        name
+    };
+    // LSPの仕様上、空の名前は許されない
+    if name.is_empty() {
+        "（見出し）".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+struct Heading {
+    level: u8,
+    symbol: DocumentSymbol,
+}
+
+/// 見出し注記からドキュメントのアウトラインを生成する
+pub fn compute_document_symbols(doc: &DocumentState) -> Vec<DocumentSymbol> {
+    let mut headings: Vec<(u8, &std::ops::Range<usize>)> = doc
+        .scopes
+        .iter()
+        .filter_map(|s| heading_level(s.deco_kind).map(|level| (level, &s.span)))
+        .collect();
+    headings.sort_by_key(|(_, span)| span.start);
+
+    let doc_end = doc.line_index.offset_to_position(&doc.text, doc.text.len());
+
+    let mut roots = Vec::new();
+    // まだ範囲の終端が確定していない見出しのスタック
+    let mut stack: Vec<Heading> = Vec::new();
+
+    for (level, span) in headings {
+        let selection_range = Range {
+            start: doc.line_index.offset_to_position(&doc.text, span.start),
+            end: doc.line_index.offset_to_position(&doc.text, span.end),
+        };
+        // セクションは見出しのある行の先頭から始まる
+        let mut section_start = selection_range.start;
+        section_start.character = 0;
+
+        // 同レベル以上の見出しが現れたら、それより深い見出しのセクションを閉じる
+        while stack.last().is_some_and(|h| h.level >= level) {
+            let closed = stack.pop().unwrap();
+            close_section(&mut stack, &mut roots, closed, section_start);
+        }
+
+        #[allow(deprecated)]
+        let symbol = DocumentSymbol {
+            name: heading_name(doc, span),
+            detail: Some(heading_detail(level).to_string()),
+            kind: SymbolKind::STRING,
+            tags: None,
+            deprecated: None,
+            range: Range {
+                start: section_start,
+                end: doc_end,
+            },
+            selection_range,
+            children: None,
+        };
+        stack.push(Heading { level, symbol });
+    }
+
+    while let Some(closed) = stack.pop() {
+        close_section(&mut stack, &mut roots, closed, doc_end);
+    }
+
+    roots
+}
+
+/// セクションの終端を確定させ、親の見出しまたは最上位に追加する
+fn close_section(
+    stack: &mut [Heading],
+    roots: &mut Vec<DocumentSymbol>,
+    mut closed: Heading,
+    end: tower_lsp::lsp_types::Position,
+) {
+    closed.symbol.range.end = end.max(closed.symbol.selection_range.end);
+    match stack.last_mut() {
+        Some(parent) => parent
+            .symbol
+            .children
+            .get_or_insert_with(Vec::new)
+            .push(closed.symbol),
+        None => roots.push(closed.symbol),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(body: &str) -> Vec<DocumentSymbol> {
+        let doc = DocumentState::parse_body(body);
+        compute_document_symbols(&doc)
+    }
+
+    #[test]
+    fn nested_headings() {
+        let symbols = symbols(
+            "第一部［＃「第一部」は大見出し］\n\
+             一［＃「一」は中見出し］\n\
+             本文\n\
+             ［＃小見出し］甲《きのえ》［＃小見出し終わり］\n\
+             二［＃「二」は中見出し］\n\
+             第二部［＃「第二部」は大見出し］\n",
+        );
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].name, "第一部");
+        assert_eq!(symbols[0].range.start.line, 2);
+        assert_eq!(symbols[0].range.end.line, 7);
+
+        let parts = symbols[0].children.as_ref().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "一");
+        assert_eq!(parts[0].range.end.line, 6);
+
+        let sections = parts[0].children.as_ref().unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].name, "甲");
+        assert_eq!(sections[0].detail.as_deref(), Some("小見出し"));
+
+        assert_eq!(symbols[1].name, "第二部");
+        assert!(symbols[1].children.is_none());
+    }
+
+    #[test]
+    fn empty_heading() {
+        let symbols = symbols("［＃大見出し］　［＃大見出し終わり］\n");
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "（見出し）");
+    }
+
+    #[test]
+    fn no_headings() {
+        assert!(symbols("吾輩は猫である。\n").is_empty());
+    }
+}
@@ -24,7 +24,7 @@ pub fn compute_folding_ranges(doc: &DocumentState) -> Vec<FoldingRange> {
 
     // 複数行ブロック注記の折り畳み
     for scope in &doc.scopes {
-        if scope.deco_kind != OwnedDecoKind::Block {
+        if !scope.deco_kind.is_block() {
             continue;
         }
         let start = doc.line_index.offset_to_position(&doc.text, scope.span.start);
@@ -0,0 +1,40 @@
+use aozora_rs_core::format_edits;
+use tower_lsp::lsp_types::TextEdit;
+
+use crate::diagnostics::to_range;
+use crate::document::DocumentState;
+
+/// 注記の表記ゆれと改行コードを正規化する編集を生成する
+pub fn compute_formatting(doc: &DocumentState) -> Vec<TextEdit> {
+    format_edits(&doc.text)
+        .into_iter()
+        .map(|edit| TextEdit {
+            range: to_range(doc, &edit.span),
+            new_text: edit.replacement,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Position;
+
+    #[test]
+    fn normalizes_annotations() {
+        let doc = DocumentState::parse_body("［＃太字］吾輩［＃太字終り］\n［＃改頁］\n");
+        let edits = compute_formatting(&doc);
+
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].range.start, Position::new(2, 9));
+        assert_eq!(edits[0].range.end, Position::new(2, 13));
+        assert_eq!(edits[0].new_text, "太字終わり");
+        assert_eq!(edits[1].new_text, "改ページ");
+    }
+
+    #[test]
+    fn nothing_to_format() {
+        let doc = DocumentState::parse_body("吾輩は猫である。\n");
+        assert!(compute_formatting(&doc).is_empty());
+    }
+}
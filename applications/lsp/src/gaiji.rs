@@ -0,0 +1,63 @@
+use std::ops::Range;
+
+use crate::document::{DocumentState, OwnedTokenKind};
+
+/// 外字注記（`※［＃…］`）1件分の情報
+pub struct GaijiNotation {
+    /// `※`から`］`までの範囲
+    pub span: Range<usize>,
+    /// 注記の中身（`［＃`と`］`の間）
+    pub description: String,
+    /// 解決できた場合のUnicode文字列
+    pub resolved: Option<String>,
+}
+
+/// 本文中の外字注記を列挙し、それぞれの解決結果を添えて返す
+pub fn gaiji_notations(doc: &DocumentState) -> Vec<GaijiNotation> {
+    doc.tokens
+        .iter()
+        .filter_map(|token| {
+            let OwnedTokenKind::Gaiji {
+                description,
+                resolved,
+                ..
+            } = &token.kind
+            else {
+                return None;
+            };
+            Some(GaijiNotation {
+                span: token.span.clone(),
+                description: description.clone(),
+                resolved: resolved.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_notations() {
+        let doc = DocumentState::parse_body(
+            "※［＃「木＋世」、第3水準1-85-56］と※［＃「存在しない字」］\n",
+        );
+        let notations = gaiji_notations(&doc);
+
+        assert_eq!(notations.len(), 2);
+        assert_eq!(
+            &doc.text[notations[0].span.clone()],
+            "※［＃「木＋世」、第3水準1-85-56］"
+        );
+        assert_eq!(notations[0].resolved.as_deref(), Some("枻"));
+        assert_eq!(notations[1].description, "「存在しない字」");
+        assert_eq!(notations[1].resolved, None);
+    }
+
+    #[test]
+    fn ignores_other_annotations() {
+        let doc = DocumentState::parse_body("※印［＃存在しない注記］\n");
+        assert!(gaiji_notations(&doc).is_empty());
+    }
+}
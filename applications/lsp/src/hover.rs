@@ -1,6 +1,6 @@
 use tower_lsp::lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position};
 
-use crate::document::{DocumentState, OwnedAnnotation, OwnedTokenKind};
+use crate::document::{DocumentState, HeaderField, OwnedAnnotation, OwnedTokenKind};
 
 /// カーソル位置に応じたホバー情報を生成する
 pub fn compute_hover(doc: &DocumentState, pos: Position) -> Option<Hover> {
@@ -15,6 +15,11 @@ pub fn compute_hover(doc: &DocumentState, pos: Position) -> Option<Hover> {
     if let Some(token) = doc.token_at_offset(offset) {
         return match &token.kind {
             OwnedTokenKind::Annotation(a) => Some(hover_annotation(a)),
+            OwnedTokenKind::Gaiji {
+                description,
+                menkuten,
+                resolved,
+            } => Some(hover_gaiji(description, *menkuten, resolved.as_deref())),
             OwnedTokenKind::Ruby(r) => Some(hover_ruby(doc, r, offset)),
             OwnedTokenKind::RubyDelimiter => Some(simple_hover(
                 "**ルビ区切り** `｜`\n\nこの後のテキストにルビの適用範囲を明示します。",
@@ -29,22 +34,40 @@ pub fn compute_hover(doc: &DocumentState, pos: Position) -> Option<Hover> {
 }
 
 fn hover_metadata(doc: &DocumentState, offset: usize) -> Option<Hover> {
-    let header = &doc.text[..doc.body_offset];
-
-    // タイトル行
-    if let Some(first_newline) = header.find('\n') {
-        if offset <= first_newline {
-            return Some(simple_hover(&format!(
-                "### タイトル\n**{}**",
-                doc.meta.title
-            )));
+    // 見出しの各行
+    if let Some((field, _)) = doc
+        .header_fields()
+        .iter()
+        .find(|(_, span)| offset >= span.start && offset <= span.end)
+    {
+        let meta = &doc.meta;
+        let value = match field {
+            HeaderField::Title => Some(&meta.title),
+            HeaderField::Subtitle => meta.subtitle.as_ref(),
+            HeaderField::OriginalTitle => meta.original_title.as_ref(),
+            HeaderField::Author => Some(&meta.author),
+            HeaderField::Translator => meta.translator.as_ref(),
+            HeaderField::Editor => meta.editor.as_ref(),
+        }?;
+        return Some(simple_hover(&format!(
+            "### {}\n**{}**",
+            field.label(),
+            value
+        )));
+    }
+
+    // 記号説明ブロックの各記号
+    if let Some(entry) = doc
+        .meta
+        .symbols
+        .iter()
+        .find(|e| offset >= e.span.start && offset <= e.span.end)
+    {
+        let mut markdown = format!("### {}\n{}", entry.symbol, entry.explanation.join("\n\n"));
+        for example in &entry.examples {
+            markdown.push_str(&format!("\n\n例：`{}`", example));
         }
-        // 著者行
-        let author_start = first_newline + 1;
-        if let Some(second_newline) = header[author_start..].find('\n')
-            && offset >= author_start && offset <= author_start + second_newline {
-                return Some(simple_hover(&format!("### 著者\n**{}**", doc.meta.author)));
-            }
+        return Some(simple_hover(&markdown));
     }
 
     // 記号説明ブロック
@@ -60,7 +83,7 @@ fn hover_metadata(doc: &DocumentState, offset: usize) -> Option<Hover> {
 
 fn hover_annotation(a: &OwnedAnnotation) -> Hover {
     let (category, description) = match a {
-        OwnedAnnotation::BackRef { description } => ("前方参照型注記", description.as_str()),
+        OwnedAnnotation::BackRef { description, .. } => ("前方参照型注記", description.as_str()),
         OwnedAnnotation::SandwichedBegin { description } => {
             ("行内挟み込み型注記（開始）", description.as_str())
         }
@@ -73,7 +96,7 @@ fn hover_annotation(a: &OwnedAnnotation) -> Hover {
         OwnedAnnotation::MultilineEnd { description } => {
             ("複数行挟み込み型注記（終了）", description.as_str())
         }
-        OwnedAnnotation::Single { description } => ("単体注記", description.as_str()),
+        OwnedAnnotation::Single { description, .. } => ("単体注記", description.as_str()),
         OwnedAnnotation::WholeLine { description } => ("行頭型注記", description.as_str()),
         OwnedAnnotation::PageDef { description } => ("ページ定義注記", description.as_str()),
         OwnedAnnotation::Unknown(s) => {
@@ -89,6 +112,18 @@ fn hover_annotation(a: &OwnedAnnotation) -> Hover {
     ))
 }
 
+fn hover_gaiji(description: &str, menkuten: Option<(u8, u8, u8)>, resolved: Option<&str>) -> Hover {
+    let mut lines = vec![
+        "### 外字注記".to_string(),
+        format!("**文字**: {}", resolved.unwrap_or("（解決できません）")),
+        format!("**注記**: {}", description),
+    ];
+    if let Some((men, ku, ten)) = menkuten {
+        lines.push(format!("**面区点**: {}-{}-{}", men, ku, ten));
+    }
+    simple_hover(&lines.join("\n"))
+}
+
 fn hover_ruby(doc: &DocumentState, ruby_text: &str, _offset: usize) -> Hover {
     // スコープからルビの対象テキストを逆引きする
     let target = doc
@@ -0,0 +1,75 @@
+use tower_lsp::lsp_types::{self, InlayHint, InlayHintLabel, InlayHintTooltip};
+
+use crate::document::DocumentState;
+use crate::gaiji::gaiji_notations;
+
+/// 指定範囲にある外字注記の直後に、解決されたUnicode文字をインレイヒントとして表示する
+pub fn compute_inlay_hints(doc: &DocumentState, range: lsp_types::Range) -> Vec<InlayHint> {
+    gaiji_notations(doc)
+        .into_iter()
+        .filter_map(|notation| {
+            let glyph = notation.resolved?;
+            let position = doc
+                .line_index
+                .offset_to_position(&doc.text, notation.span.end);
+            if position < range.start || range.end < position {
+                return None;
+            }
+            Some(InlayHint {
+                position,
+                label: InlayHintLabel::String(glyph),
+                kind: None,
+                text_edits: None,
+                tooltip: Some(InlayHintTooltip::String(format!(
+                    "外字注記：{}",
+                    notation.description
+                ))),
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::to_range;
+
+    fn hints(body: &str) -> Vec<InlayHint> {
+        let doc = DocumentState::parse_body(body);
+        let whole = to_range(&doc, &(0..doc.text.len()));
+        compute_inlay_hints(&doc, whole)
+    }
+
+    fn label_of(hint: &InlayHint) -> &str {
+        match &hint.label {
+            InlayHintLabel::String(s) => s,
+            InlayHintLabel::LabelParts(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn shows_resolved_glyph() {
+        let hints = hints("吾輩※［＃「木＋世」、第3水準1-85-56］\n");
+        assert_eq!(hints.len(), 1);
+        assert_eq!(label_of(&hints[0]), "枻");
+        // 「］」の直後に表示する
+        assert_eq!(hints[0].position.line, 2);
+        assert_eq!(hints[0].position.character, 23);
+        // 型や引数名のヒントではない
+        assert_eq!(hints[0].kind, None);
+    }
+
+    #[test]
+    fn resolves_by_menkuten_only() {
+        let hints = hints("※［＃「木＋なし」、第4水準2-15-55］\n");
+        assert_eq!(label_of(&hints[0]), "𣜿");
+    }
+
+    #[test]
+    fn no_hint_for_unresolved() {
+        assert!(hints("※［＃「存在しない字」］\n").is_empty());
+    }
+}
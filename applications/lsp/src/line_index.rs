@@ -1,4 +1,4 @@
-use tower_lsp::lsp_types::Position;
+use tower_lsp::lsp_types::{Position, Range};
 
 /// バイトオフセットとLSP Position（行番号＋UTF-16文字オフセット）を相互変換する。
 pub struct LineIndex {
@@ -35,6 +35,33 @@ impl LineIndex {
 
 
 
+    /// LSPのRangeを編集前のテキストにおけるバイト範囲に変換する
+    pub fn range_to_span(&self, text: &str, range: Range) -> std::ops::Range<usize> {
+        let start = self.position_to_offset(text, range.start);
+        let end = self.position_to_offset(text, range.end).max(start);
+        start..end
+    }
+
+    /// `span`を`new_text`で置き換える編集に合わせて行の開始位置を更新する
+    pub fn apply_edit(&mut self, span: std::ops::Range<usize>, new_text: &str) {
+        let delta = new_text.len() as isize - (span.end - span.start) as isize;
+        // 置き換えられる範囲内の改行に由来する行頭を取り除く
+        let first = self.line_starts.partition_point(|&s| s <= span.start);
+        let last = self.line_starts.partition_point(|&s| s <= span.end);
+        let inserted = new_text
+            .bytes()
+            .enumerate()
+            .filter(|(_, b)| *b == b'\n')
+            .map(|(i, _)| span.start + i + 1);
+        let tail: Vec<usize> = self.line_starts[last..]
+            .iter()
+            .map(|&s| (s as isize + delta) as usize)
+            .collect();
+        self.line_starts.truncate(first);
+        self.line_starts.extend(inserted);
+        self.line_starts.extend(tail);
+    }
+
     /// LSP PositionからバイトオフセットへXmlの変換
     pub fn position_to_offset(&self, text: &str, pos: Position) -> usize {
         let line = pos.line as usize;
@@ -116,4 +143,21 @@ mod tests {
         let back = idx.position_to_offset(text, pos);
         assert_eq!(offset, back);
     }
+
+    #[test]
+    fn apply_edit_matches_rebuild() {
+        let cases = [
+            ("春と修羅\n宮沢賢治\n", 3..6, "\n"),
+            ("春と修羅\n宮沢賢治\n", 6..16, ""),
+            ("a\nb\nc\nd", 1..5, "x\ny\nz"),
+            ("a\nb", 3..3, "\n\n"),
+        ];
+        for (text, span, new_text) in cases {
+            let mut idx = LineIndex::new(text);
+            idx.apply_edit(span.clone(), new_text);
+            let mut edited = text.to_string();
+            edited.replace_range(span, new_text);
+            assert_eq!(idx.line_starts, LineIndex::new(&edited).line_starts);
+        }
+    }
 }
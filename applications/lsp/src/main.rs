@@ -1,11 +1,23 @@
+mod code_action;
 mod completion;
+mod definition;
+mod diagnostics;
 mod document;
+mod document_highlight;
+mod document_link;
+mod document_symbol;
 mod folding;
+mod formatting;
+mod gaiji;
 mod hover;
+mod inlay_hint;
 mod line_index;
+mod preview;
 mod semantic_tokens;
 mod server;
 
+use std::sync::Arc;
+
 use dashmap::DashMap;
 use tower_lsp::{LspService, Server};
 
@@ -16,10 +28,15 @@ async fn main() {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(|client| AozoraLsp {
+    let (service, socket) = LspService::build(|client| AozoraLsp {
         client,
-        documents: DashMap::new(),
-    });
+        documents: Arc::new(DashMap::new()),
+        unparsed: DashMap::new(),
+        semantic_tokens: DashMap::new(),
+        figures: Arc::new(DashMap::new()),
+    })
+    .custom_method(preview::METHOD, AozoraLsp::preview)
+    .finish();
 
     Server::new(stdin, stdout, socket).serve(service).await;
 }
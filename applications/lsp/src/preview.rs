@@ -0,0 +1,109 @@
+use aozora_rs_core::{resolve_gaiji, retokenize, scopenize, tokenize};
+use aozora_rs_gaiji::resolve_chuki;
+use aozora_rs_xhtml::XHTMLConverter;
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::{Range, TextDocumentIdentifier};
+use winnow::LocatingSlice;
+
+use crate::diagnostics::to_range;
+use crate::document::DocumentState;
+
+/// `aozora/preview`リクエストのメソッド名
+pub const METHOD: &str = "aozora/preview";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+/// ソース上の範囲とプレビュー中の要素の対応
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewMapping {
+    pub range: Range,
+    /// 要素が含まれるXHTMLの番号（`xhtmls`の添字）
+    pub xhtml: usize,
+    /// 要素のid属性
+    pub id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewResult {
+    /// ページごとのXHTML本文
+    pub xhtmls: Vec<String>,
+    pub source_map: Vec<PreviewMapping>,
+}
+
+/// 現在のバッファ全体をXHTMLに変換し、ソースとの対応表を付けて返す
+pub fn compute_preview(doc: &DocumentState) -> PreviewResult {
+    let body = &doc.text[doc.body_offset..];
+    let mut tokens = tokenize(&mut LocatingSlice::new(body)).unwrap_or_default();
+    resolve_gaiji(&mut tokens, |c| resolve_chuki(c.clone()));
+    let ((scopes, expressions), _) = scopenize(tokens).into_tuple();
+    let (pages, _) = retokenize(expressions, scopes);
+
+    // 本文はdoc.textの部分文字列なので、対応表のバイト範囲はdoc.text上の位置になる
+    let mut converter = XHTMLConverter::with_source_map(&doc.text);
+    for page in pages {
+        converter.feed_page(page);
+    }
+    let result = converter.convert();
+
+    PreviewResult {
+        xhtmls: result.xhtmls,
+        source_map: result
+            .source_map
+            .into_iter()
+            .map(|m| PreviewMapping {
+                range: to_range(doc, &m.span),
+                xhtml: m.xhtml_id,
+                id: m.element_id,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_text_to_elements() {
+        let doc = DocumentState::parse_body("吾輩は［＃太字］猫［＃太字終わり］である。\n");
+        let preview = compute_preview(&doc);
+
+        assert_eq!(preview.xhtmls.len(), 1);
+        let cat = preview
+            .source_map
+            .iter()
+            .find(|m| m.range.start.line == 2 && m.range.start.character == 8)
+            .unwrap();
+        assert_eq!(cat.range.end.character, 9);
+        assert!(preview.xhtmls[cat.xhtml].contains(&format!("<span id=\"{}\">猫</span>", cat.id)));
+    }
+
+    #[test]
+    fn maps_gaiji_to_elements() {
+        let doc = DocumentState::parse_body("吾輩※［＃「木＋世」、第3水準1-85-56］\n");
+        let preview = compute_preview(&doc);
+
+        let gaiji = preview
+            .source_map
+            .iter()
+            .find(|m| m.range.start.line == 2 && m.range.start.character == 2)
+            .unwrap();
+        assert_eq!(gaiji.range.end.character, 23);
+        assert!(
+            preview.xhtmls[gaiji.xhtml].contains(&format!("<span id=\"{}\">枻</span>", gaiji.id))
+        );
+    }
+
+    #[test]
+    fn serializes_camel_case() {
+        let doc = DocumentState::parse_body("吾輩\n");
+        let json = serde_json::to_value(compute_preview(&doc)).unwrap();
+        assert!(json["sourceMap"][0]["id"].is_string());
+    }
+}
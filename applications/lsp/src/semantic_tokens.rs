@@ -1,9 +1,12 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use tower_lsp::lsp_types::{
-    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
-    SemanticTokensLegend,
+    self, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensEdit, SemanticTokensLegend,
 };
 
-use crate::document::{DocumentState, OwnedAnnotation, OwnedTokenKind};
+use crate::document::{DocumentState, OwnedAnnotation, OwnedDecoKind, OwnedTokenKind};
 
 /// LSPに公開するセマンティックトークンタイプ一覧
 pub const TOKEN_TYPES: &[SemanticTokenType] = &[
@@ -13,6 +16,11 @@ pub const TOKEN_TYPES: &[SemanticTokenType] = &[
     SemanticTokenType::NAMESPACE, // 3: タイトル
     SemanticTokenType::TYPE,      // 4: 著者
     SemanticTokenType::COMMENT,   // 5: 記号説明ブロック
+    SemanticTokenType::CLASS,     // 6: 見出しの本文
+    SemanticTokenType::VARIABLE,  // 7: ルビの親文字
+    SemanticTokenType::KEYWORD,   // 8: 傍点・傍線の付いた本文
+    SemanticTokenType::PARAMETER, // 9: 字下げされた本文
+    SemanticTokenType::DECORATOR, // 10: 訓点・送り仮名
 ];
 
 /// LSPに公開するセマンティックトークン修飾子一覧
@@ -20,6 +28,11 @@ pub const TOKEN_MODIFIERS: &[SemanticTokenModifier] = &[
     SemanticTokenModifier::DECLARATION,  // 0: 開始注記
     SemanticTokenModifier::MODIFICATION, // 1: 終了注記
     SemanticTokenModifier::DEPRECATED,   // 2: 未知の注記
+    SemanticTokenModifier::new("boten"), // 3: 傍点
+    SemanticTokenModifier::new("bosen"), // 4: 傍線
+    SemanticTokenModifier::new("headA"), // 5: 大見出し
+    SemanticTokenModifier::new("headB"), // 6: 中見出し
+    SemanticTokenModifier::new("headC"), // 7: 小見出し
 ];
 
 pub fn legend() -> SemanticTokensLegend {
@@ -29,15 +42,112 @@ pub fn legend() -> SemanticTokensLegend {
     }
 }
 
+/// 本文の装飾に対応するトークンの表現
+#[derive(Clone, Copy)]
+struct DecorationStyle {
+    token_type: u32,
+    modifiers: u32,
+    /// 装飾が重なったときにどのタイプを採るかの優先度
+    priority: u8,
+}
+
+/// 本文の色分けに使う装飾の範囲と表現
+type Decoration<'a> = (&'a Range<usize>, DecorationStyle);
+
+fn decoration_style(kind: OwnedDecoKind) -> Option<DecorationStyle> {
+    let (token_type, modifiers, priority) = match kind {
+        OwnedDecoKind::Ruby => (7, 0, 3),       // variable
+        OwnedDecoKind::Boten => (8, 1 << 3, 2), // keyword + boten
+        OwnedDecoKind::Bosen => (8, 1 << 4, 2), // keyword + bosen
+        OwnedDecoKind::HeadA => (6, 1 << 5, 1), // class + headA
+        OwnedDecoKind::HeadB => (6, 1 << 6, 1), // class + headB
+        OwnedDecoKind::HeadC => (6, 1 << 7, 1), // class + headC
+        OwnedDecoKind::Indent => (9, 0, 0),     // parameter
+        OwnedDecoKind::Block | OwnedDecoKind::Inline => return None,
+    };
+    Some(DecorationStyle {
+        token_type,
+        modifiers,
+        priority,
+    })
+}
+
 /// ドキュメント全体のセマンティックトークンを生成する
 pub fn compute_semantic_tokens(doc: &DocumentState) -> SemanticTokens {
+    let tokens = collect_tokens(doc, 0..doc.text.len());
+    SemanticTokens {
+        result_id: None,
+        data: encode(&tokens),
+    }
+}
+
+/// 指定範囲にかかるセマンティックトークンだけを生成する
+pub fn compute_semantic_tokens_range(
+    doc: &DocumentState,
+    range: lsp_types::Range,
+) -> SemanticTokens {
+    let span = doc.offset_at_position(range.start)..doc.offset_at_position(range.end);
+    let mut tokens = collect_tokens(doc, span);
+    tokens.retain(|t| range.start.line <= t.line && t.line <= range.end.line);
+    SemanticTokens {
+        result_id: None,
+        data: encode(&tokens),
+    }
+}
+
+/// `full/delta`で前回の結果を参照するための識別子を発行する
+pub fn next_result_id() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed).to_string()
+}
+
+/// 前回の結果から新しい結果への差分を、共通の先頭と末尾を除いた1件の編集にまとめる
+pub fn diff_semantic_tokens(
+    old: &[SemanticToken],
+    new: &[SemanticToken],
+) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    if prefix == old.len() && prefix == new.len() {
+        return Vec::new();
+    }
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    // 編集の位置と削除数は整数列の添字で数える（トークン1個につき5要素）
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: ((old.len() - prefix - suffix) * 5) as u32,
+        data: Some(new[prefix..new.len() - suffix].to_vec()),
+    }]
+}
+
+/// `span`にかかるトークンを行・位置の順に集める
+fn collect_tokens(doc: &DocumentState, span: Range<usize>) -> Vec<RawSemanticToken> {
     let mut tokens = Vec::new();
 
     // メタデータ部分のトークン
     emit_metadata_tokens(doc, &mut tokens);
 
+    // 装飾された本文の色分けに使うスコープ
+    let mut decorations: Vec<Decoration> = doc
+        .scopes
+        .iter()
+        .filter(|s| s.span.start < span.end && span.start < s.span.end)
+        .filter_map(|s| Some((&s.span, decoration_style(s.deco_kind)?)))
+        .collect();
+    decorations.sort_by_key(|(s, _)| s.start);
+    let mut pending = decorations.into_iter().peekable();
+    let mut active = Vec::new();
+
     // 本文のトークン
-    for token in &doc.tokens {
+    let first = doc.tokens.partition_point(|t| t.span.end <= span.start);
+    for token in doc.tokens[first..]
+        .iter()
+        .take_while(|t| t.span.start < span.end)
+    {
         let (token_type, modifiers) = match &token.kind {
             OwnedTokenKind::Annotation(a) => {
                 let modifier = match a {
@@ -46,75 +156,137 @@ pub fn compute_semantic_tokens(doc: &DocumentState) -> SemanticTokens {
                     OwnedAnnotation::SandwichedEnd { .. }
                     | OwnedAnnotation::MultilineEnd { .. } => 1u32 << 1, // modification
                     OwnedAnnotation::Unknown(_) => 1u32 << 2,            // deprecated
+                    OwnedAnnotation::Single { kanbun: true, .. } => {
+                        push_token(doc, &mut tokens, &token.span, 10, 0); // decorator
+                        continue;
+                    }
                     _ => 0,
                 };
                 (0u32, modifier) // macro
             }
+            // 解決できない外字は未知の注記と同じ修飾子を付ける
+            OwnedTokenKind::Gaiji { resolved, .. } => (0, (resolved.is_none() as u32) << 2),
             OwnedTokenKind::Ruby(_) => (1, 0),          // string
             OwnedTokenKind::RubyDelimiter => (2, 0),     // operator
-            OwnedTokenKind::Text | OwnedTokenKind::Br => continue,
-        };
-
-        let start = doc.line_index.offset_to_position(&doc.text, token.span.start);
-        let end = doc.line_index.offset_to_position(&doc.text, token.span.end);
-
-        // セマンティックトークンが複数行にまたがる場合、行ごとに分割する
-        if start.line == end.line {
-            tokens.push(RawSemanticToken {
-                line: start.line,
-                start_char: start.character,
-                length: end.character - start.character,
-                token_type,
-                modifiers,
-            });
-        } else {
-            // 注記が複数行にまたがることは稀だが対応する
-            // 先頭行
-            let first_line_end = doc
-                .text[token.span.start..]
-                .find('\n')
-                .map(|i| token.span.start + i)
-                .unwrap_or(token.span.end);
-            let first_end = doc.line_index.offset_to_position(&doc.text, first_line_end);
-            tokens.push(RawSemanticToken {
-                line: start.line,
-                start_char: start.character,
-                length: first_end.character - start.character,
-                token_type,
-                modifiers,
-            });
-
-            // 中間行と最終行
-            let mut current_offset = first_line_end + 1;
-            while current_offset < token.span.end {
-                let line_end = doc.text[current_offset..]
-                    .find('\n')
-                    .map(|i| current_offset + i)
-                    .unwrap_or(token.span.end);
-                let actual_end = line_end.min(token.span.end);
-                let line_start_pos = doc.line_index.offset_to_position(&doc.text, current_offset);
-                let line_end_pos = doc.line_index.offset_to_position(&doc.text, actual_end);
-                if line_end_pos.character > 0 {
-                    tokens.push(RawSemanticToken {
-                        line: line_start_pos.line,
-                        start_char: 0,
-                        length: line_end_pos.character,
-                        token_type,
-                        modifiers,
-                    });
+            OwnedTokenKind::Text => {
+                while let Some(d) = pending.next_if(|(s, _)| s.start < token.span.end) {
+                    active.push(d);
                 }
-                current_offset = line_end + 1;
+                active.retain(|(s, _)| token.span.start < s.end);
+                emit_decorated_text(doc, &mut tokens, &token.span, &active);
+                continue;
             }
-        }
+            OwnedTokenKind::Br => continue,
+        };
+        push_token(doc, &mut tokens, &token.span, token_type, modifiers);
     }
 
     // 行・位置でソート
     tokens.sort_by(|a, b| a.line.cmp(&b.line).then(a.start_char.cmp(&b.start_char)));
+    tokens
+}
+
+/// テキストを装飾の境界で区切り、区間ごとに最も優先度の高い装飾のトークンタイプと、
+/// かかっているすべての装飾の修飾子を割り当てる
+fn emit_decorated_text(
+    doc: &DocumentState,
+    tokens: &mut Vec<RawSemanticToken>,
+    span: &Range<usize>,
+    decorations: &[Decoration],
+) {
+    let covering: Vec<_> = decorations
+        .iter()
+        .filter(|(s, _)| s.start < span.end && span.start < s.end)
+        .collect();
+    if covering.is_empty() {
+        return;
+    }
+    let mut bounds = vec![span.start, span.end];
+    for (s, _) in &covering {
+        bounds.push(s.start.clamp(span.start, span.end));
+        bounds.push(s.end.clamp(span.start, span.end));
+    }
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    for w in bounds.windows(2) {
+        let styles = covering
+            .iter()
+            .filter(|(s, _)| s.start <= w[0] && w[1] <= s.end)
+            .map(|(_, style)| *style);
+        let Some(top) = styles.clone().max_by_key(|style| style.priority) else {
+            continue;
+        };
+        let modifiers = styles.fold(0, |m, style| m | style.modifiers);
+        push_token(doc, tokens, &(w[0]..w[1]), top.token_type, modifiers);
+    }
+}
 
-    // デルタエンコーディング
+/// トークンを出力する。複数行にまたがる場合は行ごとに分割する
+fn push_token(
+    doc: &DocumentState,
+    tokens: &mut Vec<RawSemanticToken>,
+    span: &Range<usize>,
+    token_type: u32,
+    modifiers: u32,
+) {
+    let start = doc.line_index.offset_to_position(&doc.text, span.start);
+    let end = doc.line_index.offset_to_position(&doc.text, span.end);
+
+    if start.line == end.line {
+        tokens.push(RawSemanticToken {
+            line: start.line,
+            start_char: start.character,
+            length: end.character - start.character,
+            token_type,
+            modifiers,
+        });
+    } else {
+        // 注記が複数行にまたがることは稀だが対応する
+        // 先頭行
+        let first_line_end = doc
+            .text[span.start..]
+            .find('\n')
+            .map(|i| span.start + i)
+            .unwrap_or(span.end);
+        let first_end = doc.line_index.offset_to_position(&doc.text, first_line_end);
+        tokens.push(RawSemanticToken {
+            line: start.line,
+            start_char: start.character,
+            length: first_end.character - start.character,
+            token_type,
+            modifiers,
+        });
+
+        // 中間行と最終行
+        let mut current_offset = first_line_end + 1;
+        while current_offset < span.end {
+            let line_end = doc.text[current_offset..]
+                .find('\n')
+                .map(|i| current_offset + i)
+                .unwrap_or(span.end);
+            let actual_end = line_end.min(span.end);
+            let line_start_pos = doc.line_index.offset_to_position(&doc.text, current_offset);
+            let line_end_pos = doc.line_index.offset_to_position(&doc.text, actual_end);
+            if line_end_pos.character > 0 {
+                tokens.push(RawSemanticToken {
+                    line: line_start_pos.line,
+                    start_char: 0,
+                    length: line_end_pos.character,
+                    token_type,
+                    modifiers,
+                });
+            }
+            current_offset = line_end + 1;
+        }
+    }
+}
+
+/// 行・位置の順に並んだトークンをデルタエンコーディングする
+fn encode(tokens: &[RawSemanticToken]) -> Vec<SemanticToken> {
     let mut prev_line = 0u32;
     let mut prev_start = 0u32;
-    let data: Vec<SemanticToken> = tokens
+    tokens
         .iter()
         .map(|t| {
             let delta_line = t.line - prev_line;
@@ -133,12 +305,7 @@ pub fn compute_semantic_tokens(doc: &DocumentState) -> SemanticTokens {
                 token_modifiers_bitset: t.modifiers,
             }
         })
-        .collect();
-
-    SemanticTokens {
-        result_id: None,
-        data,
-    }
+        .collect()
 }
 
 struct RawSemanticToken {
@@ -151,33 +318,17 @@ struct RawSemanticToken {
 
 /// メタデータ領域のセマンティックトークンを出力する
 fn emit_metadata_tokens(doc: &DocumentState, tokens: &mut Vec<RawSemanticToken>) {
-    let header = &doc.text[..doc.body_offset];
-
-    // タイトル行 (1行目)
-    if let Some(newline_pos) = header.find('\n') {
-        let title_end = doc.line_index.offset_to_position(&doc.text, newline_pos);
+    // 見出しの各行
+    for (field, span) in doc.header_fields() {
+        let start = doc.line_index.offset_to_position(&doc.text, span.start);
+        let end = doc.line_index.offset_to_position(&doc.text, span.end);
         tokens.push(RawSemanticToken {
-            line: 0,
-            start_char: 0,
-            length: title_end.character,
-            token_type: 3, // namespace
+            line: start.line,
+            start_char: start.character,
+            length: end.character - start.character,
+            token_type: if field.is_title() { 3 } else { 4 }, // namespace / type
             modifiers: 0,
         });
-
-        // 著者行 (2行目)
-        let author_start = newline_pos + 1;
-        if let Some(second_newline) = header[author_start..].find('\n') {
-            let author_end_offset = author_start + second_newline;
-            let author_start_pos = doc.line_index.offset_to_position(&doc.text, author_start);
-            let author_end_pos = doc.line_index.offset_to_position(&doc.text, author_end_offset);
-            tokens.push(RawSemanticToken {
-                line: author_start_pos.line,
-                start_char: 0,
-                length: author_end_pos.character,
-                token_type: 4, // type
-                modifiers: 0,
-            });
-        }
     }
 
     // 記号説明ブロック
@@ -202,3 +353,80 @@ fn emit_metadata_tokens(doc: &DocumentState, tokens: &mut Vec<RawSemanticToken>)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Position;
+
+    /// (行, 開始位置, 長さ, タイプ, 修飾子)の一覧に戻す
+    fn decode(data: &[SemanticToken]) -> Vec<(u32, u32, u32, u32, u32)> {
+        let (mut line, mut start) = (0, 0);
+        data.iter()
+            .map(|t| {
+                if t.delta_line > 0 {
+                    start = 0;
+                }
+                line += t.delta_line;
+                start += t.delta_start;
+                (
+                    line,
+                    start,
+                    t.length,
+                    t.token_type,
+                    t.token_modifiers_bitset,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decorated_text() {
+        let doc =
+            DocumentState::parse_body("［＃大見出し］第一章　猫《ねこ》［＃大見出し終わり］\n");
+        let tokens = decode(&compute_semantic_tokens(&doc).data);
+        // 見出しの本文と、その中のルビの親文字は別のタイプになる
+        assert!(tokens.contains(&(2, 7, 4, 6, 1 << 5)));
+        assert!(tokens.contains(&(2, 11, 1, 7, 1 << 5)));
+        assert!(tokens.contains(&(2, 12, 4, 1, 0)));
+    }
+
+    #[test]
+    fn emphasis_and_kanbun() {
+        let doc = DocumentState::parse_body(
+            "吾輩は猫である［＃「猫である」に傍点］\n学而［＃二］時習之\n",
+        );
+        let tokens = decode(&compute_semantic_tokens(&doc).data);
+        assert!(tokens.contains(&(2, 3, 4, 8, 1 << 3)));
+        assert!(tokens.contains(&(3, 2, 4, 10, 0)));
+    }
+
+    #[test]
+    fn range_only() {
+        let doc = DocumentState::parse_body(
+            "吾輩は［＃太字］猫［＃太字終わり］\n名前は［＃太字］まだ［＃太字終わり］無い\n",
+        );
+        let range = lsp_types::Range::new(Position::new(3, 0), Position::new(3, 20));
+        let tokens = decode(&compute_semantic_tokens_range(&doc, range).data);
+        assert!(!tokens.is_empty());
+        assert!(tokens.iter().all(|t| t.0 == 3));
+    }
+
+    #[test]
+    fn delta_edits() {
+        let old_doc = DocumentState::parse_body("［＃太字］吾輩［＃太字終わり］\n猫\n");
+        let new_doc = DocumentState::parse_body("［＃太字］吾輩［＃太字終わり］\n猫《ねこ》\n");
+        let old = compute_semantic_tokens(&old_doc).data;
+        let new = compute_semantic_tokens(&new_doc).data;
+        let edits = diff_semantic_tokens(&old, &new);
+        assert_eq!(edits.len(), 1);
+
+        // 編集を適用すると新しい結果と一致する
+        let mut applied = old.clone();
+        let start = edits[0].start as usize / 5;
+        let end = start + edits[0].delete_count as usize / 5;
+        applied.splice(start..end, edits[0].data.clone().unwrap());
+        assert_eq!(applied, new);
+        assert!(diff_semantic_tokens(&new, &new).is_empty());
+    }
+}
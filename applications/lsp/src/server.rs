@@ -1,17 +1,56 @@
-use dashmap::DashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use dashmap::{DashMap, mapref::entry::Entry};
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
-use crate::completion::compute_completions;
+use crate::code_action::compute_code_actions;
+use crate::completion::{compute_completions, compute_gaiji_completions};
+use crate::definition::compute_definition;
+use crate::diagnostics::{compute_diagnostics, compute_figure_diagnostics, missing_figures};
 use crate::document::DocumentState;
+use crate::document_highlight::compute_document_highlights;
+use crate::document_link::{compute_document_links, figure_refs};
+use crate::document_symbol::compute_document_symbols;
 use crate::folding::compute_folding_ranges;
+use crate::formatting::compute_formatting;
 use crate::hover::compute_hover;
-use crate::semantic_tokens::{self, compute_semantic_tokens};
+use crate::inlay_hint::compute_inlay_hints;
+use crate::line_index::LineIndex;
+use crate::preview::{PreviewParams, PreviewResult, compute_preview};
+use crate::semantic_tokens::{
+    self, compute_semantic_tokens, compute_semantic_tokens_range, diff_semantic_tokens,
+    next_result_id,
+};
+
+/// 編集中に画像の有無を確かめるまでの待ち時間
+const FIGURE_CHECK_DELAY: Duration = Duration::from_millis(500);
 
 pub struct AozoraLsp {
     pub client: Client,
-    pub documents: DashMap<Url, DocumentState>,
+    /// 編集後に遅れて画像の有無を確かめるタスクと共有する
+    pub documents: Arc<DashMap<Url, DocumentState>>,
+    /// メタデータ解析に失敗したドキュメントの本文（増分同期の適用先として保持する）
+    pub unparsed: DashMap<Url, String>,
+    /// `full/delta`の差分計算に使う、最後に返したセマンティックトークン
+    pub semantic_tokens: DashMap<Url, SemanticTokens>,
+    /// 図の注記が参照する画像を確かめた結果。開いたときと保存したとき、参照する画像が変わる編集の後に更新する
+    pub figures: Arc<DashMap<Url, FigureCheck>>,
+}
+
+/// 図の注記が参照する画像を確かめた結果
+#[derive(Default)]
+pub struct FigureCheck {
+    /// 確かめた画像のパス
+    paths: Vec<String>,
+    /// そのうち見つからなかったもの
+    missing: HashSet<String>,
+    /// 最後に予約した確認。古い確認の結果で上書きしないよう、結果を保存する前に照合する
+    generation: u64,
 }
 
 #[tower_lsp::async_trait]
@@ -19,25 +58,45 @@ impl LanguageServer for AozoraLsp {
     async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
-                text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL,
+                text_document_sync: Some(TextDocumentSyncCapability::Options(
+                    TextDocumentSyncOptions {
+                        open_close: Some(true),
+                        change: Some(TextDocumentSyncKind::INCREMENTAL),
+                        save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                        ..Default::default()
+                    },
                 )),
                 semantic_tokens_provider: Some(
                     SemanticTokensServerCapabilities::SemanticTokensOptions(
                         SemanticTokensOptions {
                             legend: semantic_tokens::legend(),
-                            full: Some(SemanticTokensFullOptions::Bool(true)),
-                            range: None,
+                            full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            range: Some(true),
                             ..Default::default()
                         },
                     ),
                 ),
                 completion_provider: Some(CompletionOptions {
-                    trigger_characters: Some(vec!["＃".to_string()]),
+                    trigger_characters: Some(vec!["＃".to_string(), "「".to_string()]),
                     ..Default::default()
                 }),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
                 folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                        ..Default::default()
+                    },
+                )),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: Some(false),
+                    work_done_progress_options: Default::default(),
+                }),
                 ..Default::default()
             },
             ..Default::default()
@@ -56,26 +115,81 @@ impl LanguageServer for AozoraLsp {
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let uri = params.text_document.uri;
-        let text = params.text_document.text;
-        self.reparse(uri, text).await;
+        let state = DocumentState::parse(params.text_document.text);
+        if let Ok(doc) = &state {
+            check_figures(&self.figures, &uri, figure_paths(doc), next_generation()).await;
+        }
+        self.store(uri, state, Some(params.text_document.version))
+            .await;
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let Some(doc) = self.documents.get(&uri) else {
+            return;
+        };
+        let paths = figure_paths(&doc);
+        drop(doc);
+        check_figures(&self.figures, &uri, paths, next_generation()).await;
+        self.publish(uri, None).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri;
-        // TextDocumentSyncKind::FULLなので全文が1つ目のイベントに入る
-        if let Some(change) = params.content_changes.into_iter().next() {
-            // 登録済みのドキュメントの場合のみリパース
-            if self.documents.contains_key(&uri) {
-                self.reparse(uri, change.text).await;
-            } else {
-                // 未登録なら新規判定を試みる
-                self.reparse(uri, change.text).await;
+        let version = Some(params.text_document.version);
+        // TextDocumentSyncKind::INCREMENTALなので変更は順番に適用する
+        let mut changes = params.content_changes.into_iter();
+        // 書き込みロックを持ったまま書き換えるので、並行するリクエストは適用後の状態を待つ
+        let unparsed = match self.documents.entry(uri.clone()) {
+            Entry::Occupied(mut entry) => {
+                let unparsed = changes.by_ref().find_map(|change| {
+                    entry
+                        .get_mut()
+                        .apply_change(change.range, &change.text)
+                        .err()
+                });
+                if unparsed.is_some() {
+                    entry.remove();
+                }
+                unparsed
             }
+            Entry::Vacant(_) => match self.unparsed.remove(&uri) {
+                Some((_, text)) => Some(text),
+                None => return,
+            },
+        };
+        let Some(text) = unparsed else {
+            self.publish(uri.clone(), version).await;
+            self.schedule_figure_check(uri);
+            return;
+        };
+        let mut state: std::result::Result<DocumentState, String> = Err(text);
+        for change in changes {
+            state = match state {
+                Ok(mut doc) => doc.apply_change(change.range, &change.text).map(|()| doc),
+                Err(mut text) => {
+                    if let Some(range) = change.range {
+                        let span = LineIndex::new(&text).range_to_span(&text, range);
+                        text.replace_range(span, &change.text);
+                    } else {
+                        text = change.text;
+                    }
+                    DocumentState::parse(text)
+                }
+            };
         }
+        self.store(uri.clone(), state, version).await;
+        self.schedule_figure_check(uri);
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        self.documents.remove(&params.text_document.uri);
+        let uri = params.text_document.uri;
+        self.documents.remove(&uri);
+        self.unparsed.remove(&uri);
+        self.semantic_tokens.remove(&uri);
+        self.figures.remove(&uri);
+        // 閉じたドキュメントの診断は消去する
+        self.client.publish_diagnostics(uri, Vec::new(), None).await;
     }
 
     async fn semantic_tokens_full(
@@ -86,10 +200,48 @@ impl LanguageServer for AozoraLsp {
         let Some(doc) = self.documents.get(uri) else {
             return Ok(None);
         };
-        let tokens = compute_semantic_tokens(&doc);
+        let mut tokens = compute_semantic_tokens(&doc);
+        tokens.result_id = Some(next_result_id());
+        self.semantic_tokens.insert(uri.clone(), tokens.clone());
         Ok(Some(SemanticTokensResult::Tokens(tokens)))
     }
 
+    async fn semantic_tokens_full_delta(
+        &self,
+        params: SemanticTokensDeltaParams,
+    ) -> Result<Option<SemanticTokensFullDeltaResult>> {
+        let uri = &params.text_document.uri;
+        let Some(doc) = self.documents.get(uri) else {
+            return Ok(None);
+        };
+        let mut tokens = compute_semantic_tokens(&doc);
+        tokens.result_id = Some(next_result_id());
+        let previous = self.semantic_tokens.insert(uri.clone(), tokens.clone());
+
+        // 前回の結果が手元に残っていれば差分だけを返す
+        match previous {
+            Some(previous) if previous.result_id == Some(params.previous_result_id) => Ok(Some(
+                SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                    result_id: tokens.result_id,
+                    edits: diff_semantic_tokens(&previous.data, &tokens.data),
+                }),
+            )),
+            _ => Ok(Some(SemanticTokensFullDeltaResult::Tokens(tokens))),
+        }
+    }
+
+    async fn semantic_tokens_range(
+        &self,
+        params: SemanticTokensRangeParams,
+    ) -> Result<Option<SemanticTokensRangeResult>> {
+        let uri = &params.text_document.uri;
+        let Some(doc) = self.documents.get(uri) else {
+            return Ok(None);
+        };
+        let tokens = compute_semantic_tokens_range(&doc, params.range);
+        Ok(Some(SemanticTokensRangeResult::Tokens(tokens)))
+    }
+
     async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
         let uri = &params.text_document_position.text_document.uri;
         let pos = params.text_document_position.position;
@@ -122,6 +274,12 @@ impl LanguageServer for AozoraLsp {
             end: replace_end,
         };
 
+        // `※［＃「`は外字注記として字形の説明などから検索する
+        if doc.text[..trigger_pos].ends_with('※') && prefix.starts_with('「') {
+            let list = compute_gaiji_completions(prefix, replace_range);
+            return Ok(Some(CompletionResponse::List(list)));
+        }
+
         let items = compute_completions(&doc, prefix, replace_range);
         Ok(Some(CompletionResponse::Array(items)))
     }
@@ -135,6 +293,30 @@ impl LanguageServer for AozoraLsp {
         Ok(compute_hover(&doc, pos))
     }
 
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+        let Some(doc) = self.documents.get(uri) else {
+            return Ok(None);
+        };
+        Ok(compute_definition(&doc, uri, pos))
+    }
+
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+        let Some(doc) = self.documents.get(uri) else {
+            return Ok(None);
+        };
+        Ok(compute_document_highlights(&doc, pos))
+    }
+
     async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
         let uri = &params.text_document.uri;
         let Some(doc) = self.documents.get(uri) else {
@@ -143,18 +325,174 @@ impl LanguageServer for AozoraLsp {
         let ranges = compute_folding_ranges(&doc);
         Ok(Some(ranges))
     }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let uri = &params.text_document.uri;
+        let Some(doc) = self.documents.get(uri) else {
+            return Ok(None);
+        };
+        let symbols = compute_document_symbols(&doc);
+        Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = &params.text_document.uri;
+        let Some(doc) = self.documents.get(uri) else {
+            return Ok(None);
+        };
+        let actions = compute_code_actions(&doc, uri, params.range);
+        Ok(Some(actions))
+    }
+
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        let uri = &params.text_document.uri;
+        let Some(doc) = self.documents.get(uri) else {
+            return Ok(None);
+        };
+        Ok(Some(compute_document_links(&doc, uri)))
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = &params.text_document.uri;
+        let Some(doc) = self.documents.get(uri) else {
+            return Ok(None);
+        };
+        Ok(Some(compute_formatting(&doc)))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let uri = &params.text_document.uri;
+        let Some(doc) = self.documents.get(uri) else {
+            return Ok(None);
+        };
+        Ok(Some(compute_inlay_hints(&doc, params.range)))
+    }
 }
 
 impl AozoraLsp {
-    async fn reparse(&self, uri: Url, text: String) {
-        match DocumentState::parse(text) {
-            Some(state) => {
-                self.documents.insert(uri, state);
+    /// `aozora/preview`: 現在のバッファのXHTMLプレビューを返す
+    pub async fn preview(&self, params: PreviewParams) -> Result<Option<PreviewResult>> {
+        let Some(doc) = self.documents.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+        Ok(Some(compute_preview(&doc)))
+    }
+
+    /// 図の注記が参照する画像の存在を、ブロッキングしてよいスレッドで確認する
+    /// 参照する画像が前回確かめたものから変わっていれば、少し待ってから確かめ直して診断を更新する。
+    /// 待つ間に次の編集があれば、そちらの確認に任せる
+    fn schedule_figure_check(&self, uri: Url) {
+        let Some(paths) = self.documents.get(&uri).map(|doc| figure_paths(&doc)) else {
+            return;
+        };
+        let generation = {
+            let mut check = self.figures.entry(uri.clone()).or_default();
+            if check.paths == paths {
+                return;
+            }
+            check.generation = next_generation();
+            check.generation
+        };
+        let client = self.client.clone();
+        let documents = self.documents.clone();
+        let figures = self.figures.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(FIGURE_CHECK_DELAY).await;
+            if figures
+                .get(&uri)
+                .is_none_or(|check| check.generation != generation)
+            {
+                return;
+            }
+            let Some(paths) = documents.get(&uri).map(|doc| figure_paths(&doc)) else {
+                return;
+            };
+            if check_figures(&figures, &uri, paths, generation).await {
+                publish(&client, &documents, &figures, uri, None).await;
+            }
+        });
+    }
+
+    async fn store(
+        &self,
+        uri: Url,
+        state: std::result::Result<DocumentState, String>,
+        version: Option<i32>,
+    ) {
+        match state {
+            Ok(state) => {
+                self.documents.insert(uri.clone(), state);
             }
-            None => {
-                // メタデータ解析失敗 → 青空文庫書式ではないので無視
-                self.documents.remove(&uri);
+            Err(text) => {
+                // メタデータ解析失敗 → 青空文庫書式ではないので本文だけ保持する
+                self.unparsed.insert(uri.clone(), text);
             }
         }
+        self.publish(uri, version).await;
+    }
+
+    async fn publish(&self, uri: Url, version: Option<i32>) {
+        publish(&self.client, &self.documents, &self.figures, uri, version).await;
     }
 }
+
+async fn publish(
+    client: &Client,
+    documents: &DashMap<Url, DocumentState>,
+    figures: &DashMap<Url, FigureCheck>,
+    uri: Url,
+    version: Option<i32>,
+) {
+    let diagnostics = documents
+        .get(&uri)
+        .map(|doc| {
+            let mut diagnostics = compute_diagnostics(&doc, &uri);
+            let check = figures.get(&uri);
+            let missing = check.as_ref().map(|c| &c.missing);
+            diagnostics.extend(compute_figure_diagnostics(
+                &doc,
+                &uri,
+                missing.unwrap_or(&HashSet::new()),
+            ));
+            diagnostics
+        })
+        .unwrap_or_default();
+    client.publish_diagnostics(uri, diagnostics, version).await;
+}
+
+/// 画像の有無を確かめて保存する。`generation`より新しい確認が予約されていれば保存せずにfalseを返す
+async fn check_figures(
+    figures: &DashMap<Url, FigureCheck>,
+    uri: &Url,
+    paths: Vec<String>,
+    generation: u64,
+) -> bool {
+    let target = uri.clone();
+    let checked = paths.clone();
+    let missing = tokio::task::spawn_blocking(move || missing_figures(&target, checked))
+        .await
+        .unwrap_or_default();
+    let mut check = figures.entry(uri.clone()).or_default();
+    if check.generation > generation {
+        return false;
+    }
+    *check = FigureCheck {
+        paths,
+        missing,
+        generation,
+    };
+    true
+}
+
+/// 画像の確認を予約するたびに、より新しいことを示す番号を発行する
+fn next_generation() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+fn figure_paths(doc: &DocumentState) -> Vec<String> {
+    figure_refs(doc).into_iter().map(|f| f.path).collect()
+}
@@ -4,12 +4,12 @@ use std::{
 };
 
 use aozora_rs::{
-    AozoraError, Dependencies,
+    AozoraError, Dependencies, Diagnostic,
     internal::{
         Annotation, AozoraTokenKind, EpubSetting, from_aozora_zip, parse_meta, retokenize,
         retokenized_to_xhtml, scopenize, tokenize,
     },
-    utf8tify_all_gaiji,
+    utf8tify_all_gaiji_with_map,
 };
 use encoding_rs::SHIFT_JIS;
 use serde::Serialize;
@@ -56,18 +56,23 @@ pub fn analyse_per_work(s: &str) -> Result<WorkAnalyse, AozoraError> {
     let read_duration = read_instant.elapsed();
 
     let gaiji_instant = Instant::now();
-    let gaiji_converted = utf8tify_all_gaiji(original_text.as_str());
+    let gaiji_converted = utf8tify_all_gaiji_with_map(original_text.as_str());
     let gaiji_duration = gaiji_instant.elapsed();
-    let (s, invalid_gaijis) = gaiji_converted;
+    let (s, invalid_gaijis, offset_map) = gaiji_converted;
     let mut s_slice = s.as_ref();
 
     let meta_instant = Instant::now();
     let meta = parse_meta(&mut s_slice).map_err(AozoraError::from)?;
     let meta_duration = meta_instant.elapsed();
+    let body_offset = s.len() - s_slice.len();
 
     let title_owned = meta.title.to_string();
     let author_owned = meta.author.to_string();
 
+    let to_original = |d: Diagnostic| {
+        d.map_spans(|s| offset_map.span_to_original(&(s.start + body_offset..s.end + body_offset)))
+    };
+
     let tokenize_instant = Instant::now();
     let tokenized = tokenize(&mut LocatingSlice::new(s_slice)).map_err(AozoraError::from)?;
     let tokenize_duration = tokenize_instant.elapsed();
@@ -152,11 +157,15 @@ pub fn analyse_per_work(s: &str) -> Result<WorkAnalyse, AozoraError> {
         token_count,
         byte_count: s.len(),
 
+        // 外字を変換する前の原文上の位置で報告する
         scopenize_errors: scopenize_errors
             .iter()
-            .map(|s| s.display(s_slice))
+            .map(|e| to_original(e.to_diagnostic(s_slice)).display(&original_text))
+            .collect(),
+        retokenize_errors: retokenize_errors
+            .iter()
+            .map(|e| to_original(e.to_diagnostic()).display(&original_text))
             .collect(),
-        retokenize_errors: retokenize_errors.iter().map(|s| s.to_string()).collect(),
         invalid_gaiji: invalid_gaijis.iter().map(|s| s.to_string()).collect(),
         invalid_notes,
 
@@ -1,8 +1,8 @@
 use std::io::Cursor;
 
 use aozora_rs::{
-    AozoraDocument, AozoraWarning, AozoraZip, WritingDirection, internal::AozoraMeta,
-    utf8tify_all_gaiji,
+    AozoraDocument, AozoraWarning, AozoraZip, OffsetMap, Severity, WritingDirection,
+    internal::AozoraMeta, utf8tify_all_gaiji, utf8tify_all_gaiji_with_map,
 };
 use wasm_bindgen::{JsError, prelude::wasm_bindgen};
 
@@ -19,21 +19,87 @@ pub struct StandaloneXHTML {
     pub occured_error: String,
 }
 
-pub fn warnings_to_string(errors: Vec<AozoraWarning>, original: impl AsRef<str>) -> String {
+/// JavaScript側に渡す診断です。
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct WasmDiagnostic {
+    #[wasm_bindgen(getter_with_clone)]
+    pub code: String,
+    /// "error"または"warning"
+    #[wasm_bindgen(getter_with_clone)]
+    pub severity: String,
+    /// 既定の日本語の文言
+    #[wasm_bindgen(getter_with_clone)]
+    pub message: String,
+    /// 文言に埋め込まれた値。`code`ごとに文言を用意して翻訳する場合に使う
+    #[wasm_bindgen(getter_with_clone)]
+    pub args: Vec<String>,
+    /// 外字を変換する前の入力上の範囲（UTF-8のバイト位置）。位置を持たない診断ではundefined
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    #[wasm_bindgen(getter_with_clone)]
+    pub fix: Option<String>,
+}
+
+/// 警告の位置を入力上の位置に戻すための情報
+pub struct WarningSource<'a> {
+    /// 外字を変換する前の入力
+    pub original: &'a str,
+    /// 外字を変換した後のテキスト
+    pub converted: &'a str,
+    /// `converted`の中で警告が発生した本文の開始位置
+    pub body_offset: usize,
+    pub map: &'a OffsetMap,
+}
+
+pub fn warnings_to_diagnostics(
+    errors: &[AozoraWarning],
+    source: &WarningSource,
+) -> Vec<WasmDiagnostic> {
+    errors
+        .iter()
+        .map(|e| {
+            let d = e.to_original_diagnostic(source.converted, source.body_offset, source.map);
+            WasmDiagnostic {
+                code: d.code.to_string(),
+                severity: match d.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                }
+                .to_string(),
+                message: d.message.text(),
+                args: d.message.args,
+                start: d.span.as_ref().map(|s| s.start),
+                end: d.span.as_ref().map(|s| s.end),
+                fix: d.fix.map(|f| f.title.text()),
+            }
+        })
+        .collect()
+}
+
+pub fn warnings_to_string(errors: &[AozoraWarning], source: &WarningSource) -> String {
     errors
         .iter()
-        .map(|e| e.display(original.as_ref()))
+        .map(|e| {
+            e.display_original(
+                source.converted,
+                source.body_offset,
+                source.original,
+                source.map,
+            )
+        })
         .collect::<Vec<String>>()
         .join("\n")
 }
 
 #[wasm_bindgen]
 pub fn generate_embedding_xhtml(from: &str, delimiter: &str) -> Result<StandaloneXHTML, JsError> {
-    let (converted, _) = utf8tify_all_gaiji(from);
+    let (converted, _, map) = utf8tify_all_gaiji_with_map(from);
     let doc = AozoraDocument::from_str_and_meta(
         AozoraMeta {
             title: "MOCK_TITLE",
             author: "MOCK_AUTHOR",
+            ..Default::default()
         },
         &converted,
         None,
@@ -41,7 +107,15 @@ pub fn generate_embedding_xhtml(from: &str, delimiter: &str) -> Result<Standalon
     let (xhtml, errors) = doc.xhtml()?;
     Ok(StandaloneXHTML {
         result: xhtml.xhtmls.join(delimiter),
-        occured_error: warnings_to_string(errors, converted),
+        occured_error: warnings_to_string(
+            &errors,
+            &WarningSource {
+                original: from,
+                converted: &converted,
+                body_offset: 0,
+                map: &map,
+            },
+        ),
     })
 }
 
@@ -55,18 +129,28 @@ pub struct BookData {
     pub xhtmls: Vec<String>,
     #[wasm_bindgen(getter_with_clone)]
     pub errors: String,
+    #[wasm_bindgen(getter_with_clone)]
+    pub diagnostics: Vec<WasmDiagnostic>,
 }
 
 #[wasm_bindgen]
 pub fn parse_to_book_data(from: &str) -> Result<BookData, JsError> {
-    let (converted, _) = utf8tify_all_gaiji(from);
+    let (converted, _, map) = utf8tify_all_gaiji_with_map(from);
     let doc = AozoraDocument::from_str(&converted, None)?;
     let (xhtml, errors) = doc.xhtml()?;
+    let source = WarningSource {
+        original: from,
+        converted: &converted,
+        // 本文はconvertedの部分文字列なので、ポインタの差が本文の開始位置になる
+        body_offset: doc.text.as_ptr() as usize - converted.as_ptr() as usize,
+        map: &map,
+    };
     Ok(BookData {
         title: doc.meta.title.into(),
         author: doc.meta.author.into(),
         xhtmls: xhtml.xhtmls,
-        errors: warnings_to_string(errors, converted),
+        diagnostics: warnings_to_diagnostics(&errors, &source),
+        errors: warnings_to_string(&errors, &source),
     })
 }
 
@@ -81,7 +165,10 @@ pub fn build_epub_bytes(
 ) -> Result<Vec<u8>, JsError> {
     let mut acc = Cursor::new(Vec::new());
     let enc = match encoding {
+        "auto" | "" => aozora_rs_zip::Encoding::Auto,
         "utf8" | "utf-8" | "UTF8" | "UTF-8" => aozora_rs_zip::Encoding::Utf8,
+        "euc-jp" | "EUC-JP" => aozora_rs_zip::Encoding::EucJp,
+        "iso-2022-jp" | "ISO-2022-JP" => aozora_rs_zip::Encoding::Iso2022Jp,
         _ => aozora_rs_zip::Encoding::ShiftJIS,
     };
 
@@ -89,10 +176,10 @@ pub fn build_epub_bytes(
         .map_err(|_| JsError::new("ZipからのAozoraDocumentの構築に失敗しました"))?;
 
     let txt = if consider_gaiji {
-        let (utf8ified, _) = utf8tify_all_gaiji(&azz.txt);
+        let (utf8ified, _) = utf8tify_all_gaiji(azz.txt());
         utf8ified.into_owned()
     } else {
-        azz.txt.clone()
+        azz.txt().to_owned()
     };
 
     let direction = if is_vertical {